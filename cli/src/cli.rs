@@ -1,9 +1,22 @@
+use config::Language;
+use search::SyntaxScope;
 use std::path::PathBuf;
-use structopt::StructOpt;
+use structopt::{
+    clap::{AppSettings, Shell},
+    StructOpt,
+};
+
+use crate::output::OutputFormat;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "wicli", about = "Find it")]
 pub struct Wicli {
+    #[structopt(
+        long,
+        global = true,
+        help = "Threads to search with, overrides the threads key of the config"
+    )]
+    pub threads: Option<usize>,
     #[structopt(subcommand)]
     pub commands: Command,
 }
@@ -13,7 +26,245 @@ pub enum Command {
     #[structopt(about = "Manage your file sources")]
     Sources(SourcesSubcommand),
     #[structopt(about = "Search your sources")]
-    Search { term: String },
+    Search(Box<SearchCommand>),
+    #[structopt(about = "Find the files most similar to a given one across your sources")]
+    Similar {
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+        #[structopt(long, default_value = "10", help = "How many files to show")]
+        limit: usize,
+        #[structopt(
+            long,
+            default_value = "table",
+            possible_values = &["table", "json", "markdown"],
+            help = "Format used to print the similar files"
+        )]
+        format: OutputFormat,
+    },
+    #[structopt(about = "Find duplicate and nearly duplicate files across your sources")]
+    Dupes {
+        #[structopt(
+            long,
+            default_value = "0.8",
+            help = "How similar, from 0 to 1, files need to be to count as near duplicates"
+        )]
+        threshold: f64,
+        #[structopt(
+            long,
+            default_value = "table",
+            possible_values = &["table", "json", "markdown"],
+            help = "Format used to print the duplicates"
+        )]
+        format: OutputFormat,
+    },
+    #[structopt(
+        about = "Find where functions, types and constants are defined in Rust, TypeScript, Python and Go files"
+    )]
+    Symbols {
+        name: String,
+        #[structopt(
+            long,
+            default_value = "table",
+            possible_values = &["table", "json", "markdown"],
+            help = "Format used to print the symbols"
+        )]
+        format: OutputFormat,
+    },
+    #[structopt(about = "Audit your sources for keys, tokens and passwords before sharing them")]
+    Secrets {
+        #[structopt(
+            long,
+            parse(from_os_str),
+            help = "File of regular expressions, one per line, matching the secrets, paths or rules to ignore"
+        )]
+        allowlist: Option<PathBuf>,
+        #[structopt(
+            long,
+            default_value = "table",
+            possible_values = &["table", "json", "markdown"],
+            help = "Format used to print the secrets found"
+        )]
+        format: OutputFormat,
+    },
+    #[structopt(about = "Collect the TODO, FIXME, HACK and XXX markers across your sources")]
+    Todos {
+        #[structopt(
+            long,
+            default_value = "table",
+            possible_values = &["table", "json", "markdown"],
+            help = "Format used to print the markers"
+        )]
+        format: OutputFormat,
+    },
+    #[structopt(
+        about = "Find the sources that depend on a crate, npm package, Python package or Go module"
+    )]
+    Deps {
+        name: String,
+        #[structopt(
+            long,
+            default_value = "table",
+            possible_values = &["table", "json", "markdown"],
+            help = "Format used to print the dependencies"
+        )]
+        format: OutputFormat,
+    },
+    #[structopt(
+        about = "Report the license files and SPDX headers of your sources, and the files missing headers"
+    )]
+    Licenses {
+        #[structopt(
+            long,
+            default_value = "table",
+            possible_values = &["table", "json", "markdown"],
+            help = "Format used to print the report"
+        )]
+        format: OutputFormat,
+    },
+    #[structopt(
+        about = "Print the shell completions, searched terms are completed from your sources"
+    )]
+    Completions {
+        #[structopt(possible_values = &Shell::variants(), case_insensitive = true)]
+        shell: Shell,
+    },
+    #[structopt(
+        about = "Print what a search query could be completed with",
+        setting = AppSettings::Hidden
+    )]
+    Complete {
+        #[structopt(default_value = "")]
+        query: String,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub struct SearchCommand {
+    #[structopt(
+        help = "Text to search for, quote words to find a \"phrase\" or words near each other with \"term1 term2\"~N. Leave it out to type it with completions"
+    )]
+    pub term: Option<String>,
+    #[structopt(
+        short = "e",
+        long = "pattern",
+        number_of_values = 1,
+        help = "Search for any of these patterns, can be repeated"
+    )]
+    pub patterns: Vec<String>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Read the patterns to search for from a file, one per line"
+    )]
+    pub patterns_file: Option<PathBuf>,
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Search files of any kind for these bytes, written in hex like \"de ad be ef\", can be repeated"
+    )]
+    pub hex: Vec<String>,
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Search files of any kind for these bytes, escaped like \"\\x7fELF\", can be repeated"
+    )]
+    pub bytes: Vec<String>,
+    #[structopt(
+        long,
+        help = "Match the nodes of JSON, YAML and TOML files with path:, key: and value: clauses, like \"key:timeout value:>30\""
+    )]
+    pub structured: bool,
+    #[structopt(
+        long,
+        help = "Match the nodes of JSON, YAML and TOML files at a path, like \".dependencies.serde\""
+    )]
+    pub path_expr: Option<String>,
+    #[structopt(
+        long = "in",
+        possible_values = &["comments", "strings", "code"],
+        conflicts_with_all = &["hex", "bytes", "structured", "path-expr", "log", "since", "until", "invert"],
+        help = "Only search the comments, string literals or code of Rust, TypeScript, Python and Go files"
+    )]
+    pub scope: Option<SyntaxScope>,
+    #[structopt(
+        long,
+        help = "Search log files entry by entry, a match shows up with the rest of its entry like a stack trace"
+    )]
+    pub log: bool,
+    #[structopt(
+        long,
+        help = "Only search log entries from this time on, like \"2026-10-01T10:00\""
+    )]
+    pub since: Option<String>,
+    #[structopt(
+        long,
+        help = "Only search log entries before this time, like \"2026-10-02\""
+    )]
+    pub until: Option<String>,
+    #[structopt(
+        short = "U",
+        long,
+        help = "Treat the patterns as regular expressions that can span multiple lines"
+    )]
+    pub multiline: bool,
+    #[structopt(
+        long,
+        help = "Match regardless of accents, \"configuracao\" finds \"configuração\""
+    )]
+    pub ignore_diacritics: bool,
+    #[structopt(
+        long = "ref",
+        help = "Search a branch, tag or commit of your git sources"
+    )]
+    pub git_ref: Option<String>,
+    #[structopt(
+        long,
+        requires = "git-ref",
+        help = "Fetch the ref from the origin remote of your git sources before searching"
+    )]
+    pub fetch: bool,
+    #[structopt(
+        long,
+        conflicts_with = "invert",
+        help = "Show the files that don't contain the term"
+    )]
+    pub files_without_match: bool,
+    #[structopt(long, help = "Show the lines that don't contain the term")]
+    pub invert: bool,
+    #[structopt(long, help = "Stop after this many files with matches")]
+    pub max_results: Option<usize>,
+    #[structopt(long, help = "Stop after this many matches")]
+    pub max_matches: Option<usize>,
+    #[structopt(long, help = "Stop after reading this many files")]
+    pub max_files: Option<usize>,
+    #[structopt(
+        long,
+        default_value = "10M",
+        help = "Skip files larger than this, like 512K or 1G, 0 reads files of any size"
+    )]
+    pub max_filesize: String,
+    #[structopt(
+        long,
+        help = "How many directories deep to search each source, 1 only searches the files at its root"
+    )]
+    pub max_depth: Option<usize>,
+    #[structopt(
+        long,
+        default_value = "30",
+        help = "Stop reading files after this many seconds, 0 waits for the whole search"
+    )]
+    pub timeout: u64,
+    #[structopt(long, help = "Print match statistics instead of browsing the results")]
+    pub stats: bool,
+    #[structopt(long, help = "Rescan the sources instead of reusing cached results")]
+    pub no_cache: bool,
+    #[structopt(
+        long,
+        default_value = "table",
+        possible_values = &["table", "json", "markdown"],
+        help = "Format used to print the statistics"
+    )]
+    pub format: OutputFormat,
 }
 
 #[derive(Debug, StructOpt)]
@@ -29,4 +280,13 @@ pub enum SourcesSubcommand {
         #[structopt(parse(from_os_str))]
         path: PathBuf,
     },
+    #[structopt(
+        about = "Set the language used to search the prose of a source, leave it out to stop using one"
+    )]
+    Analyzer {
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+        #[structopt(possible_values = &["english", "portuguese"])]
+        language: Option<Language>,
+    },
 }
@@ -0,0 +1,94 @@
+use std::io::{self, Write};
+
+use structopt::{clap::Shell, StructOpt};
+
+use crate::cli::Wicli;
+
+/// Completes the terms of `wicli search` with whatever `wicli complete` finds,
+/// falling back to the generated completions for everything else
+const BASH_SEARCH_COMPLETIONS: &str = r#"
+_wicli_search() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    case "${prev}" in
+        --ref|--patterns-file|--format)
+            _wicli "$@"
+            return 0
+            ;;
+    esac
+    if [[ "${COMP_WORDS[1]}" == "search" && ${COMP_CWORD} -gt 1 && "${cur}" != -* ]]; then
+        local IFS=$'\n'
+        COMPREPLY=($(wicli complete -- "${cur}" 2>/dev/null))
+        return 0
+    fi
+    _wicli "$@"
+}
+complete -F _wicli_search -o bashdefault -o default wicli
+"#;
+
+const FISH_SEARCH_COMPLETIONS: &str = r#"
+complete -c wicli -n "__fish_seen_subcommand_from search" -f -a "(wicli complete -- (commandline -ct) 2>/dev/null)"
+"#;
+
+pub struct Completions;
+
+impl Completions {
+    pub fn print(shell: Shell) -> anyhow::Result<()> {
+        Self::write(shell, &mut io::stdout())?;
+
+        Ok(())
+    }
+
+    fn write(shell: Shell, buffer: &mut impl Write) -> io::Result<()> {
+        match shell {
+            Shell::Zsh => {
+                writeln!(buffer, "autoload -U +X bashcompinit && bashcompinit")?;
+                Self::generate(Shell::Bash, buffer);
+                write!(buffer, "{}", BASH_SEARCH_COMPLETIONS)
+            }
+            Shell::Bash => {
+                Self::generate(shell, buffer);
+                write!(buffer, "{}", BASH_SEARCH_COMPLETIONS)
+            }
+            Shell::Fish => {
+                Self::generate(shell, buffer);
+                write!(buffer, "{}", FISH_SEARCH_COMPLETIONS)
+            }
+            _ => {
+                Self::generate(shell, buffer);
+                Ok(())
+            }
+        }
+    }
+
+    fn generate(shell: Shell, buffer: &mut impl Write) {
+        Wicli::clap().gen_completions_to("wicli", shell, buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(shell: Shell) -> String {
+        let mut buffer = Vec::new();
+        Completions::write(shell, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn it_hooks_into_the_generated_bash_completions() {
+        for shell in [Shell::Bash, Shell::Zsh] {
+            let script = script(shell);
+
+            // The hook falls back to the generated function, and is registered last
+            // so it's the one the shell calls
+            let generated = script.find("_wicli() {").unwrap();
+            let hook = script.find("_wicli_search() {").unwrap();
+            let registered = script.rfind("complete -F").unwrap();
+            assert!(generated < hook && hook < registered);
+            assert!(script[registered..].starts_with("complete -F _wicli_search "));
+        }
+        assert!(script(Shell::Fish).contains("wicli complete -- (commandline -ct)"));
+    }
+}
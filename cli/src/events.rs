@@ -1,11 +1,18 @@
 use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyModifiers};
 
-use crate::results_state::ResultsState;
+use crate::{prompt_state::PromptState, results_state::ResultsState};
 use anyhow::Result;
 pub struct Events;
 
 type ExitApp = bool;
 
+#[derive(Debug, PartialEq)]
+pub enum PromptEvent {
+    Editing,
+    Submitted,
+    Cancelled,
+}
+
 impl Events {
     pub fn read(results_state: &mut ResultsState) -> Result<ExitApp> {
         if let Event::Key(event) = read()? {
@@ -24,6 +31,21 @@ impl Events {
                 code: KeyCode::Down,
                 ..
             } => results_state.next(),
+            KeyEvent {
+                code: KeyCode::Char(digit @ '1'..='9'),
+                ..
+            } if results_state.items.is_empty() => {
+                let index = digit as usize - '1' as usize;
+                return results_state.pick_suggestion(index);
+            }
+            KeyEvent {
+                code: KeyCode::Char('s'),
+                modifiers: KeyModifiers::NONE,
+            } => return results_state.pick_similar(),
+            KeyEvent {
+                code: KeyCode::Char('d'),
+                modifiers: KeyModifiers::NONE,
+            } => results_state.toggle_diagnostics(),
             KeyEvent {
                 code: KeyCode::Esc, ..
             } => return true,
@@ -36,15 +58,66 @@ impl Events {
         };
         false
     }
+
+    pub fn read_prompt(prompt_state: &mut PromptState) -> Result<PromptEvent> {
+        if let Event::Key(event) = read()? {
+            return Ok(Self::handle_prompt_event(event, prompt_state));
+        }
+        Ok(PromptEvent::Editing)
+    }
+
+    fn handle_prompt_event(key_event: KeyEvent, prompt_state: &mut PromptState) -> PromptEvent {
+        match key_event {
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => return PromptEvent::Cancelled,
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } if prompt_state.list_state.selected().is_some() => prompt_state.accept(),
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } if !prompt_state.query.is_empty() => return PromptEvent::Submitted,
+            KeyEvent {
+                code: KeyCode::Tab, ..
+            } => prompt_state.accept(),
+            KeyEvent {
+                code: KeyCode::Up, ..
+            } => prompt_state.previous(),
+            KeyEvent {
+                code: KeyCode::Down,
+                ..
+            } => prompt_state.next(),
+            KeyEvent {
+                code: KeyCode::Backspace,
+                ..
+            } => prompt_state.pop(),
+            KeyEvent {
+                code: KeyCode::Char(character),
+                ..
+            } => prompt_state.push(character),
+            _ => {}
+        };
+        PromptEvent::Editing
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::results_state::fake_results;
+    use search::{Completer, Diagnostic, DiagnosticKind, Suggestion, Vocabulary};
+    use std::path::PathBuf;
 
     #[test]
     fn it_goes_forward_when_pressing_down_not_exiting_app() {
-        let results = [String::from("a_string")];
+        let results = fake_results(&["a_string"]);
         let mut results_state = ResultsState::from_results(&results);
         let key_event = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
 
@@ -56,7 +129,7 @@ mod tests {
 
     #[test]
     fn it_goes_backward_when_pressing_down_not_exiting_app() {
-        let results = [String::from("a_string")];
+        let results = fake_results(&["a_string"]);
         let mut results_state = ResultsState::from_results(&results);
         let key_event = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
 
@@ -68,7 +141,7 @@ mod tests {
 
     #[test]
     fn it_ignores_keys_with_no_action() {
-        let results = [String::from("a_string")];
+        let results = fake_results(&["a_string"]);
         let mut results_state = ResultsState::from_results(&results);
         let key_event = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE);
 
@@ -85,9 +158,110 @@ mod tests {
         assert_eq!(results_state.list_state.selected(), None);
     }
 
+    #[test]
+    fn it_picks_a_suggestion_when_pressing_its_number() {
+        let results = fake_results(&[]);
+        let suggestions = vec![Suggestion {
+            pattern: 0,
+            term: "timeout".to_string(),
+            distance: 1,
+            frequency: 3,
+        }];
+        let mut results_state = ResultsState::from_results(&results).with_suggestions(&suggestions);
+
+        let exit_app = Events::handle_event(
+            KeyEvent::new(KeyCode::Char('2'), KeyModifiers::NONE),
+            &mut results_state,
+        );
+
+        assert!(!exit_app);
+        assert_eq!(results_state.rerun, None);
+
+        let exit_app = Events::handle_event(
+            KeyEvent::new(KeyCode::Char('1'), KeyModifiers::NONE),
+            &mut results_state,
+        );
+
+        assert!(exit_app);
+        assert_eq!(results_state.rerun, Some(&suggestions[0]));
+    }
+
+    #[test]
+    fn it_toggles_the_diagnostics_when_there_are_any() {
+        let results = fake_results(&["a"]);
+        let diagnostics = vec![Diagnostic {
+            kind: DiagnosticKind::PermissionDenied,
+            path: PathBuf::from("secret.txt"),
+            message: String::from("Permission denied (os error 13)"),
+        }];
+        let mut results_state = ResultsState::from_results(&results);
+
+        Events::handle_event(
+            KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE),
+            &mut results_state,
+        );
+        assert!(!results_state.show_diagnostics);
+
+        let mut results_state = results_state.with_diagnostics(&diagnostics);
+        for shown in [true, false] {
+            let exit_app = Events::handle_event(
+                KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE),
+                &mut results_state,
+            );
+            assert!(!exit_app);
+            assert_eq!(results_state.show_diagnostics, shown);
+        }
+    }
+
+    #[test]
+    fn it_completes_and_submits_the_prompt() {
+        let mut vocabulary = Vocabulary::default();
+        vocabulary.add("timeout");
+        let completer = Completer::new(Vec::new(), vocabulary);
+        let mut prompt_state = PromptState::new(&completer);
+
+        for code in [KeyCode::Enter, KeyCode::Char('t'), KeyCode::Tab]
+            .iter()
+            .copied()
+        {
+            let prompt_event = Events::handle_prompt_event(
+                KeyEvent::new(code, KeyModifiers::NONE),
+                &mut prompt_state,
+            );
+            assert_eq!(prompt_event, PromptEvent::Editing);
+        }
+        assert_eq!(prompt_state.query, "timeout");
+
+        let prompt_event = Events::handle_prompt_event(
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+            &mut prompt_state,
+        );
+
+        assert_eq!(prompt_event, PromptEvent::Submitted);
+    }
+
+    #[test]
+    fn it_picks_the_selected_result_when_pressing_s() {
+        let results = fake_results(&["result0", "result1"]);
+        let mut results_state = ResultsState::from_results(&results);
+        let key_event = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE);
+
+        let exit_app = Events::handle_event(key_event, &mut results_state);
+
+        assert!(!exit_app);
+        assert_eq!(results_state.similar_to, None);
+
+        results_state.next();
+        results_state.next();
+        let exit_app = Events::handle_event(key_event, &mut results_state);
+
+        assert!(exit_app);
+        assert_eq!(results_state.similar_to, Some(&results[1]));
+    }
+
     #[test]
     fn it_exits_app_when_pressing_escape() {
-        let results = [];
+        let results = fake_results(&[]);
         let mut results_state = ResultsState::from_results(&results);
         let key_event = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
 
@@ -98,7 +272,7 @@ mod tests {
 
     #[test]
     fn it_exits_app_when_pressing_ctrl_c() {
-        let results = [];
+        let results = fake_results(&[]);
         let mut results_state = ResultsState::from_results(&results);
         let key_event = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
 
@@ -1,21 +1,39 @@
 mod cli;
+mod completions;
 mod events;
+mod output;
+mod prompt_state;
 mod results_state;
 mod ui;
 
 use anyhow::Result;
-use cli::{Command, SourcesSubcommand, Wicli};
-use config::Config;
-use events::Events;
+use cli::{Command, SearchCommand, SourcesSubcommand, Wicli};
+use completions::Completions;
+use config::{Config, Configuration};
+use events::{Events, PromptEvent};
+use output::Output;
+use prompt_state::PromptState;
 use results_state::ResultsState;
-use search::Search;
+use search::{
+    init_thread_pool, parse_escaped, parse_hex, parse_size, read_patterns, Allowlist, Completer,
+    Dependencies, Duplicates, Licenses, Limits, MatchMode, PathExpr, ResultCache, Search,
+    SearchOptions, SearchResults, Secrets, Similarity, StructuredQuery, Symbols, TimeRange, Todos,
+    Vocabulary,
+};
 use sources::SourceManager;
+use std::time::Duration;
 use structopt::StructOpt;
 use ui::UI;
 
+const MAX_SHELL_COMPLETIONS: usize = 20;
+const MAX_SIMILAR_FILES: usize = 10;
+
 fn main() -> Result<()> {
     let opt = Wicli::from_args();
     let mut config = Config::default();
+    if let Some(threads) = opt.threads.or(config.config_map.threads) {
+        init_thread_pool(threads)?;
+    }
 
     match opt.commands {
         Command::Sources(sources) => {
@@ -26,19 +44,205 @@ fn main() -> Result<()> {
                 }
                 SourcesSubcommand::List => sources_manager.list(&config)?,
                 SourcesSubcommand::Remove { path } => sources_manager.remove(&mut config, path)?,
+                SourcesSubcommand::Analyzer { path, language } => {
+                    sources_manager.set_analyzer(&mut config, path, language)?
+                }
             };
         }
-        Command::Search { term } => {
-            let results = Search::by_term(&config, term)?;
+        Command::Search(search_command) => {
+            let SearchCommand {
+                term,
+                patterns,
+                patterns_file,
+                hex,
+                bytes,
+                structured,
+                path_expr,
+                scope,
+                log,
+                since,
+                until,
+                multiline,
+                ignore_diacritics,
+                git_ref,
+                fetch,
+                files_without_match,
+                invert,
+                max_results,
+                max_matches,
+                max_files,
+                max_filesize,
+                max_depth,
+                timeout,
+                stats,
+                no_cache,
+                format,
+            } = *search_command;
+            if let (true, Some(git_ref)) = (fetch, &git_ref) {
+                Search::fetch(&config, git_ref)?;
+            }
+            let cache = ResultCache::default();
+            let search = |config: &Config, options: &SearchOptions| {
+                if no_cache {
+                    Search::with_options(config, options)
+                } else {
+                    cache.search(config, options)
+                }
+            };
+            let mode = match (files_without_match, invert) {
+                (true, _) => MatchMode::FilesWithoutMatch,
+                (_, true) => MatchMode::Inverted,
+                _ => MatchMode::Matching,
+            };
+            let mut patterns = term.into_iter().chain(patterns).collect::<Vec<String>>();
+            for query in &patterns {
+                config.add_to_history(query);
+            }
+            if let Some(patterns_file) = patterns_file {
+                patterns.extend(read_patterns(patterns_file)?);
+            }
+            let byte_patterns = hex
+                .iter()
+                .map(|hex| parse_hex(hex))
+                .chain(bytes.iter().map(|bytes| parse_escaped(bytes)))
+                .collect::<Result<Vec<Vec<u8>>>>()?;
+            let structured = match (structured, path_expr) {
+                (false, None) => None,
+                (_, path_expr) => {
+                    let structured_query = StructuredQuery::parse(&patterns.join(" "))?;
+                    Some(match path_expr {
+                        Some(path_expr) => structured_query.with_path(PathExpr::parse(&path_expr)?),
+                        None => structured_query,
+                    })
+                }
+            };
+            let log = match (log, &since, &until) {
+                (false, None, None) => None,
+                _ => Some(TimeRange::parse(since.as_deref(), until.as_deref())?),
+            };
+            let limits = Limits {
+                max_results,
+                max_matches,
+                max_files,
+                max_file_size: Some(parse_size(&max_filesize)?).filter(|size| *size > 0),
+                max_depth,
+                timeout: Some(Duration::from_secs(timeout)).filter(|timeout| !timeout.is_zero()),
+            };
+            let mut options = SearchOptions {
+                patterns,
+                git_ref,
+                mode,
+                multiline,
+                ignore_diacritics,
+                byte_patterns,
+                structured,
+                log,
+                scope,
+                limits,
+            };
+            if stats {
+                // The statistics are the ones of this run, never of a cached one
+                let search_results = Search::with_options(&config, &options)?;
+                return Output::stats(&search_results, format);
+            }
 
             let mut ui = UI::default();
-            let mut results_state = ResultsState::from_results(&results);
+            if options.patterns.is_empty()
+                && options.byte_patterns.is_empty()
+                && options.structured.is_none()
+                && !matches!(&options.log, Some(time_range) if !time_range.is_unbounded())
+            {
+                let vocabulary = if no_cache {
+                    Vocabulary::build(&config, options.git_ref.as_deref())
+                } else {
+                    cache.vocabulary(&config, options.git_ref.as_deref())
+                };
+                let completer = Completer::new(config.get_history().clone(), vocabulary);
+                let mut prompt_state = PromptState::new(&completer);
+                ui.clear()?;
+                loop {
+                    ui.draw_prompt(&mut prompt_state)?;
+                    match Events::read_prompt(&mut prompt_state)? {
+                        PromptEvent::Editing => {}
+                        PromptEvent::Submitted => break,
+                        PromptEvent::Cancelled => return Ok(()),
+                    }
+                }
+                config.add_to_history(&prompt_state.query);
+                options.patterns.push(prompt_state.query);
+            }
+            let mut search_results = search(&config, &options)?;
+            let mut error = None;
             loop {
-                ui.draw(&mut results_state)?;
-                let should_exit = Events::read(&mut results_state)?;
-                if should_exit {
-                    break;
+                let mut results_state = ResultsState::from_results(&search_results.results)
+                    .with_suggestions(&search_results.suggestions)
+                    .with_limits_hit(&search_results.stats.limits_hit)
+                    .with_diagnostics(&search_results.diagnostics)
+                    .with_error(error.as_deref());
+                ui.clear()?;
+                loop {
+                    ui.draw(&mut results_state)?;
+                    let should_exit = Events::read(&mut results_state)?;
+                    if should_exit {
+                        break;
+                    }
                 }
+                search_results = match (results_state.rerun, results_state.similar_to) {
+                    (Some(suggestion), _) => {
+                        config.add_to_history(&suggestion.term);
+                        options.patterns[suggestion.pattern] = suggestion.term.clone();
+                        error = None;
+                        search(&config, &options)?
+                    }
+                    // A file that can't be compared is reported in the results pane,
+                    // the session goes on
+                    (_, Some(result)) => {
+                        match Similarity::find(&config, &result.path, MAX_SIMILAR_FILES) {
+                            Ok(similar_files) => {
+                                error = None;
+                                SearchResults::from(similar_files)
+                            }
+                            Err(similarity_error) => {
+                                error = Some(similarity_error.to_string());
+                                SearchResults::default()
+                            }
+                        }
+                    }
+                    _ => break,
+                };
+            }
+        }
+        Command::Similar {
+            path,
+            limit,
+            format,
+        } => Output::similar(&Similarity::find(&config, &path, limit)?, format)?,
+        Command::Dupes { threshold, format } => {
+            Output::duplicates(&Duplicates::find(&config, threshold), format)?
+        }
+        Command::Symbols { name, format } => {
+            Output::symbols(&Symbols::find(&config, &name), format)?
+        }
+        Command::Secrets { allowlist, format } => {
+            let allowlist = match allowlist {
+                Some(allowlist) => Allowlist::read(allowlist)?,
+                None => Allowlist::default(),
+            };
+            Output::secrets(&Secrets::find(&config, &allowlist), format)?
+        }
+        Command::Todos { format } => Output::todos(&Todos::find(&config), format)?,
+        Command::Deps { name, format } => {
+            Output::dependencies(&Dependencies::find(&config, &name), format)?
+        }
+        Command::Licenses { format } => Output::licenses(&Licenses::find(&config), format)?,
+        Command::Completions { shell } => Completions::print(shell)?,
+        Command::Complete { query } => {
+            let completer = Completer::new(
+                config.get_history().clone(),
+                ResultCache::default().vocabulary(&config, None),
+            );
+            for candidate in completer.complete(&query, MAX_SHELL_COMPLETIONS) {
+                println!("{}", candidate.text);
             }
         }
     }
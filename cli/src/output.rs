@@ -0,0 +1,598 @@
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use search::{
+    DependencyReport, Diagnostic, DiagnosticKind, DuplicateGroup, DuplicateKind, DuplicateReport,
+    GroupStats, LicenseReport, SearchResults, SearchStats, SecretReport, SimilarFiles,
+    SymbolReport, TodoGroup, TodoReport,
+};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Markdown,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "markdown" => Ok(OutputFormat::Markdown),
+            _ => Err(anyhow!("Unknown output format: {}", format)),
+        }
+    }
+}
+
+/// Statistics of a search followed by its diagnostics, as printed in JSON
+#[derive(Serialize)]
+struct StatsReport<'search> {
+    #[serde(flatten)]
+    stats: &'search SearchStats,
+    diagnostics: &'search [Diagnostic],
+}
+
+pub struct Output;
+
+impl Output {
+    pub fn stats(search_results: &SearchResults, format: OutputFormat) -> Result<()> {
+        let stats = &search_results.stats;
+        match format {
+            OutputFormat::Json => {
+                let report = StatsReport {
+                    stats,
+                    diagnostics: &search_results.diagnostics,
+                };
+                println!("{}", serde_json::to_string_pretty(&report)?)
+            }
+            OutputFormat::Table | OutputFormat::Markdown => {
+                println!(
+                    "{}\n",
+                    Self::tabular(
+                        format,
+                        &["Files scanned", "Bytes read", "Files skipped", "Elapsed"],
+                        &[vec![
+                            stats.files_scanned.to_string(),
+                            stats.bytes_read.to_string(),
+                            stats.files_skipped.to_string(),
+                            format!("{:.2?}", stats.elapsed),
+                        ]],
+                    )
+                );
+                if !stats.limits_hit.is_empty() {
+                    let limits_hit = stats
+                        .limits_hit
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<String>>();
+                    println!("Stopped early by: {}\n", limits_hit.join(", "));
+                }
+                if let Some(summary) = Self::diagnostics_summary(&search_results.diagnostics) {
+                    println!("Unable to search: {}\n", summary);
+                }
+                println!(
+                    "{}\n",
+                    Self::group_table(format, "Source", &stats.by_source)
+                );
+                println!(
+                    "{}\n",
+                    Self::group_table(format, "Directory", &stats.by_directory)
+                );
+                println!(
+                    "{}",
+                    Self::group_table(format, "Extension", &stats.by_extension)
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn similar(similar_files: &SimilarFiles, format: OutputFormat) -> Result<()> {
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(similar_files)?),
+            OutputFormat::Table | OutputFormat::Markdown => {
+                let rows = similar_files
+                    .files
+                    .iter()
+                    .map(|similar_file| {
+                        vec![
+                            format!("{:.0}%", similar_file.score * 100.0),
+                            similar_file.path.display().to_string(),
+                        ]
+                    })
+                    .collect::<Vec<Vec<String>>>();
+                println!("{}", Self::tabular(format, &["Similarity", "File"], &rows));
+                if let Some(summary) = Self::diagnostics_summary(&similar_files.diagnostics) {
+                    println!("\nUnable to read: {}", summary);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn duplicates(report: &DuplicateReport, format: OutputFormat) -> Result<()> {
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+            OutputFormat::Table | OutputFormat::Markdown => {
+                println!("{}", Self::duplicates_table(&report.groups, format));
+                if let Some(summary) = Self::diagnostics_summary(&report.diagnostics) {
+                    println!("\nUnable to read: {}", summary);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn symbols(report: &SymbolReport, format: OutputFormat) -> Result<()> {
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+            OutputFormat::Table | OutputFormat::Markdown => {
+                let rows = report
+                    .symbols
+                    .iter()
+                    .map(|symbol| {
+                        vec![
+                            symbol.kind.to_string(),
+                            symbol.scope.clone().unwrap_or_default(),
+                            format!("{}:{}", symbol.path.display(), symbol.line),
+                            symbol.definition.clone(),
+                        ]
+                    })
+                    .collect::<Vec<Vec<String>>>();
+                println!(
+                    "{}",
+                    Self::tabular(format, &["Kind", "Scope", "Location", "Definition"], &rows)
+                );
+                if let Some(summary) = Self::diagnostics_summary(&report.diagnostics) {
+                    println!("\nUnable to read: {}", summary);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn secrets(report: &SecretReport, format: OutputFormat) -> Result<()> {
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+            OutputFormat::Table | OutputFormat::Markdown => {
+                let rows = report
+                    .findings
+                    .iter()
+                    .map(|finding| {
+                        vec![
+                            finding.severity.to_string(),
+                            finding.rule.clone(),
+                            format!("{}:{}", finding.path.display(), finding.line),
+                            finding.preview.clone(),
+                        ]
+                    })
+                    .collect::<Vec<Vec<String>>>();
+                println!(
+                    "{}",
+                    Self::tabular(format, &["Severity", "Rule", "Location", "Preview"], &rows)
+                );
+                if let Some(summary) = Self::diagnostics_summary(&report.diagnostics) {
+                    println!("\nUnable to read: {}", summary);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn dependencies(report: &DependencyReport, format: OutputFormat) -> Result<()> {
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+            OutputFormat::Table | OutputFormat::Markdown => {
+                let rows = report
+                    .dependencies
+                    .iter()
+                    .map(|dependency| {
+                        vec![
+                            dependency.source.display().to_string(),
+                            dependency.ecosystem.to_string(),
+                            dependency.name.clone(),
+                            dependency.version.clone(),
+                            dependency.section.clone(),
+                            dependency.manifest.display().to_string(),
+                        ]
+                    })
+                    .collect::<Vec<Vec<String>>>();
+                println!(
+                    "{}",
+                    Self::tabular(
+                        format,
+                        &[
+                            "Source",
+                            "Ecosystem",
+                            "Name",
+                            "Version",
+                            "Section",
+                            "Manifest"
+                        ],
+                        &rows
+                    )
+                );
+                if let Some(summary) = Self::diagnostics_summary(&report.diagnostics) {
+                    println!("\nUnable to read: {}", summary);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn licenses(reports: &[LicenseReport], format: OutputFormat) -> Result<()> {
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(reports)?),
+            OutputFormat::Table | OutputFormat::Markdown => {
+                println!("{}", Self::licenses_tables(reports, format))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn licenses_tables(reports: &[LicenseReport], format: OutputFormat) -> String {
+        let relative = |report: &LicenseReport, path: &std::path::Path| {
+            path.strip_prefix(&report.source)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+
+        reports
+            .iter()
+            .map(|report| {
+                let license_files = report
+                    .license_files
+                    .iter()
+                    .map(|license_file| {
+                        let scope = match license_file.vendored {
+                            true => "vendored",
+                            false => "source",
+                        };
+                        vec![
+                            license_file.license.clone(),
+                            scope.to_string(),
+                            relative(report, &license_file.path),
+                        ]
+                    })
+                    .collect::<Vec<Vec<String>>>();
+                let headers = report
+                    .headers
+                    .iter()
+                    .map(|(license, files)| vec![license.clone(), files.to_string()])
+                    .collect::<Vec<Vec<String>>>();
+                let missing_headers = report
+                    .missing_headers
+                    .iter()
+                    .map(|path| vec![relative(report, path)])
+                    .collect::<Vec<Vec<String>>>();
+                let heading = match format {
+                    OutputFormat::Markdown => format!("## {}", report.source.display()),
+                    _ => format!("Source: {}", report.source.display()),
+                };
+
+                let mut sections = vec![
+                    heading,
+                    Self::tabular(format, &["License", "Scope", "File"], &license_files),
+                    Self::tabular(format, &["SPDX header", "Files"], &headers),
+                    Self::tabular(format, &["Missing SPDX header"], &missing_headers),
+                ];
+                if let Some(summary) = Self::diagnostics_summary(&report.diagnostics) {
+                    sections.push(format!("Unable to read: {}", summary));
+                }
+
+                sections.join("\n\n")
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    pub fn todos(report: &TodoReport, format: OutputFormat) -> Result<()> {
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+            OutputFormat::Table | OutputFormat::Markdown => {
+                println!("{}", Self::todos_tables(&report.groups, format));
+                if let Some(summary) = Self::diagnostics_summary(&report.diagnostics) {
+                    println!("\nUnable to read: {}", summary);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn todos_tables(groups: &[TodoGroup], format: OutputFormat) -> String {
+        groups
+            .iter()
+            .map(|group| {
+                let rows = group
+                    .todos
+                    .iter()
+                    .map(|todo| {
+                        let path = todo.path.strip_prefix(&group.source).unwrap_or(&todo.path);
+                        vec![
+                            todo.marker.clone(),
+                            format!("{}:{}", path.display(), todo.line),
+                            todo.author.clone().unwrap_or_default(),
+                            todo.text.clone(),
+                        ]
+                    })
+                    .collect::<Vec<Vec<String>>>();
+                let heading = match format {
+                    OutputFormat::Markdown => format!("## {}", group.source.display()),
+                    _ => format!("Source: {}", group.source.display()),
+                };
+                format!(
+                    "{}\n\n{}",
+                    heading,
+                    Self::tabular(format, &["Marker", "Location", "Author", "Text"], &rows)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    fn duplicates_table(groups: &[DuplicateGroup], format: OutputFormat) -> String {
+        let rows = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(index, group)| {
+                let kind = match group.kind {
+                    DuplicateKind::Exact => "exact",
+                    DuplicateKind::Near => "near",
+                };
+                group.files.iter().map(move |file| {
+                    vec![
+                        (index + 1).to_string(),
+                        kind.to_string(),
+                        format!("{:.0}%", group.similarity * 100.0),
+                        file.size.to_string(),
+                        file.path.display().to_string(),
+                    ]
+                })
+            })
+            .collect::<Vec<Vec<String>>>();
+
+        Self::tabular(
+            format,
+            &["Group", "Kind", "Similarity", "Bytes", "File"],
+            &rows,
+        )
+    }
+
+    fn group_table<K: GroupKey>(
+        format: OutputFormat,
+        title: &str,
+        groups: &BTreeMap<K, GroupStats>,
+    ) -> String {
+        let rows = groups
+            .iter()
+            .map(|(key, group)| {
+                vec![
+                    key.label(),
+                    group.files.to_string(),
+                    group.matches.to_string(),
+                ]
+            })
+            .collect::<Vec<Vec<String>>>();
+
+        Self::tabular(format, &[title, "Files", "Matches"], &rows)
+    }
+
+    /// How many diagnostics of each kind, like "2 unreadable files, 1 parse error"
+    fn diagnostics_summary(diagnostics: &[Diagnostic]) -> Option<String> {
+        if diagnostics.is_empty() {
+            return None;
+        }
+        let mut by_kind = BTreeMap::<DiagnosticKind, usize>::new();
+        for diagnostic in diagnostics {
+            *by_kind.entry(diagnostic.kind).or_default() += 1;
+        }
+
+        Some(
+            by_kind
+                .into_iter()
+                .map(|(kind, count)| kind.describe(count))
+                .collect::<Vec<String>>()
+                .join(", "),
+        )
+    }
+
+    fn tabular<H: Display>(format: OutputFormat, headers: &[H], rows: &[Vec<String>]) -> String {
+        match format {
+            OutputFormat::Markdown => Self::markdown_table(headers, rows),
+            _ => Self::table(headers, rows),
+        }
+    }
+
+    fn markdown_table<H: Display>(headers: &[H], rows: &[Vec<String>]) -> String {
+        let row = |cells: Vec<String>| {
+            let cells = cells
+                .iter()
+                .map(|cell| cell.replace('|', "\\|"))
+                .collect::<Vec<String>>();
+            format!("| {} |", cells.join(" | "))
+        };
+        let headers = headers
+            .iter()
+            .map(|header| header.to_string())
+            .collect::<Vec<String>>();
+        let separator = vec![String::from("---"); headers.len()];
+
+        std::iter::once(row(headers))
+            .chain(std::iter::once(row(separator)))
+            .chain(rows.iter().map(|cells| row(cells.clone())))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    pub fn table<H: Display>(headers: &[H], rows: &[Vec<String>]) -> String {
+        let headers = headers
+            .iter()
+            .map(|header| header.to_string())
+            .collect::<Vec<String>>();
+        let widths = headers
+            .iter()
+            .enumerate()
+            .map(|(column, header)| {
+                rows.iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| cell.chars().count())
+                    .chain(std::iter::once(header.chars().count()))
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<usize>>();
+
+        std::iter::once(&headers)
+            .chain(rows.iter())
+            .map(|row| {
+                row.iter()
+                    .zip(widths.iter())
+                    .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                    .collect::<Vec<String>>()
+                    .join("  ")
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+trait GroupKey {
+    fn label(&self) -> String;
+}
+
+impl GroupKey for std::path::PathBuf {
+    fn label(&self) -> String {
+        self.display().to_string()
+    }
+}
+
+impl GroupKey for String {
+    fn label(&self) -> String {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use search::{DuplicateFile, Todo};
+    use std::path::PathBuf;
+
+    #[test]
+    fn it_aligns_the_table_columns() {
+        let table = Output::table(
+            &["Source", "Files"],
+            &[
+                vec![String::from("a"), String::from("1")],
+                vec![String::from("long_source"), String::from("10")],
+            ],
+        );
+
+        assert_eq!(table, "Source       Files\na            1\nlong_source  10");
+    }
+
+    #[test]
+    fn it_lists_every_file_of_the_duplicate_groups() {
+        let file = |path: &str, size: u64| DuplicateFile {
+            source: PathBuf::from("source"),
+            path: PathBuf::from(path),
+            size,
+        };
+        let groups = vec![
+            DuplicateGroup {
+                kind: DuplicateKind::Exact,
+                similarity: 1.0,
+                files: vec![file("a/LICENSE", 100), file("b/LICENSE", 100)],
+            },
+            DuplicateGroup {
+                kind: DuplicateKind::Near,
+                similarity: 0.85,
+                files: vec![file("a/nginx.conf", 20), file("b/nginx.conf", 21)],
+            },
+        ];
+
+        let table = Output::duplicates_table(&groups, OutputFormat::Table);
+
+        assert_eq!(
+            table,
+            "Group  Kind   Similarity  Bytes  File\n\
+             1      exact  100%        100    a/LICENSE\n\
+             1      exact  100%        100    b/LICENSE\n\
+             2      near   85%         20     a/nginx.conf\n\
+             2      near   85%         21     b/nginx.conf"
+        );
+    }
+
+    #[test]
+    fn it_groups_the_todos_by_source_in_markdown() {
+        let groups = vec![TodoGroup {
+            source: PathBuf::from("source"),
+            todos: vec![Todo {
+                path: PathBuf::from("source/src/main.rs"),
+                line: 3,
+                marker: String::from("TODO"),
+                text: String::from("split a | b"),
+                author: Some(String::from("Ada")),
+            }],
+        }];
+
+        let markdown = Output::todos_tables(&groups, OutputFormat::Markdown);
+
+        assert_eq!(
+            markdown,
+            "## source\n\n\
+             | Marker | Location | Author | Text |\n\
+             | --- | --- | --- | --- |\n\
+             | TODO | src/main.rs:3 | Ada | split a \\| b |"
+        );
+    }
+
+    #[test]
+    fn it_summarizes_the_diagnostics_by_kind() {
+        let diagnostic = |kind, path: &str| Diagnostic {
+            kind,
+            path: PathBuf::from(path),
+            message: String::new(),
+        };
+        let diagnostics = [
+            diagnostic(DiagnosticKind::ParseError, "a.json"),
+            diagnostic(DiagnosticKind::UnreadableFile, "b.txt"),
+            diagnostic(DiagnosticKind::ParseError, "c.yaml"),
+        ];
+
+        assert_eq!(
+            Output::diagnostics_summary(&diagnostics),
+            Some(String::from("1 unreadable file, 2 parse errors"))
+        );
+        assert_eq!(Output::diagnostics_summary(&[]), None);
+    }
+
+    #[test]
+    fn it_parses_the_output_format() {
+        assert_eq!(OutputFormat::from_str("json").unwrap(), OutputFormat::Json);
+        assert_eq!(
+            OutputFormat::from_str("table").unwrap(),
+            OutputFormat::Table
+        );
+        assert_eq!(
+            OutputFormat::from_str("markdown").unwrap(),
+            OutputFormat::Markdown
+        );
+        assert!(OutputFormat::from_str("yaml").is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use search::{Candidate, Completer};
+use tui::widgets::ListState;
+
+const MAX_CANDIDATES: usize = 10;
+
+#[derive(Debug)]
+pub struct PromptState<'completer> {
+    pub query: String,
+    pub candidates: Vec<Candidate>,
+    pub list_state: ListState,
+    completer: &'completer Completer,
+}
+
+impl<'completer> PromptState<'completer> {
+    pub fn new(completer: &'completer Completer) -> Self {
+        Self {
+            query: String::new(),
+            candidates: completer.complete("", MAX_CANDIDATES),
+            list_state: ListState::default(),
+            completer,
+        }
+    }
+
+    pub fn push(&mut self, character: char) {
+        self.query.push(character);
+        self.refresh();
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.refresh();
+    }
+
+    /// Replaces the query with the selected candidate, or the first one when none is selected
+    pub fn accept(&mut self) {
+        let index = self.list_state.selected().unwrap_or(0);
+        if let Some(candidate) = self.candidates.get(index) {
+            self.query = candidate.text.clone();
+            self.refresh();
+        }
+    }
+
+    pub fn next(&mut self) {
+        if self.candidates.is_empty() {
+            return;
+        }
+        let i = match self.list_state.selected() {
+            Some(i) if i + 1 < self.candidates.len() => i + 1,
+            _ => 0,
+        };
+        self.list_state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        if self.candidates.is_empty() {
+            return;
+        }
+        let i = match self.list_state.selected() {
+            Some(0) | None => self.candidates.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.list_state.select(Some(i));
+    }
+
+    fn refresh(&mut self) {
+        self.candidates = self.completer.complete(&self.query, MAX_CANDIDATES);
+        self.list_state.select(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use search::Vocabulary;
+
+    fn completer() -> Completer {
+        let mut vocabulary = Vocabulary::default();
+        vocabulary.add("timeout timeouts");
+        Completer::new(vec!["retries".to_string()], vocabulary)
+    }
+
+    #[test]
+    fn it_completes_while_typing() {
+        let completer = completer();
+        let mut prompt_state = PromptState::new(&completer);
+        assert_eq!(prompt_state.candidates.len(), 1);
+
+        prompt_state.push('t');
+        prompt_state.push('i');
+
+        assert_eq!(prompt_state.query, "ti");
+        assert_eq!(prompt_state.candidates.len(), 2);
+
+        prompt_state.pop();
+        prompt_state.pop();
+
+        assert_eq!(prompt_state.candidates[0].text, "retries");
+    }
+
+    #[test]
+    fn it_accepts_the_selected_candidate() {
+        let completer = completer();
+        let mut prompt_state = PromptState::new(&completer);
+        prompt_state.push('t');
+
+        prompt_state.previous();
+        prompt_state.accept();
+
+        assert_eq!(prompt_state.query, "timeouts");
+        assert_eq!(prompt_state.list_state.selected(), None);
+    }
+}
@@ -1,20 +1,85 @@
+use search::{Diagnostic, Limit, SearchResult, Suggestion};
 use tui::widgets::ListState;
 
 #[derive(Debug)]
 pub struct ResultsState<'results> {
     pub list_state: ListState,
-    pub items: &'results [String],
+    pub items: &'results [SearchResult],
+    /// Terms to offer when there are no results
+    pub suggestions: &'results [Suggestion],
+    /// Limits that cut the search short, some files might be missing
+    pub limits_hit: &'results [Limit],
+    /// Files and sources the search couldn't read
+    pub diagnostics: &'results [Diagnostic],
+    /// Show the diagnostics instead of the selected file
+    pub show_diagnostics: bool,
+    /// Why the last action had no results, like a file that couldn't be compared
+    pub error: Option<&'results str>,
+    /// Suggestion picked to search again with
+    pub rerun: Option<&'results Suggestion>,
+    /// Result to look for similar files of
+    pub similar_to: Option<&'results SearchResult>,
 }
 
 impl<'results> ResultsState<'results> {
-    pub fn from_results(results: &'results [String]) -> Self {
+    pub fn from_results(results: &'results [SearchResult]) -> Self {
         Self {
             list_state: ListState::default(),
             items: results,
+            suggestions: &[],
+            limits_hit: &[],
+            diagnostics: &[],
+            show_diagnostics: false,
+            error: None,
+            rerun: None,
+            similar_to: None,
         }
     }
 
+    pub fn with_suggestions(mut self, suggestions: &'results [Suggestion]) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
+
+    pub fn with_limits_hit(mut self, limits_hit: &'results [Limit]) -> Self {
+        self.limits_hit = limits_hit;
+        self
+    }
+
+    pub fn with_diagnostics(mut self, diagnostics: &'results [Diagnostic]) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    pub fn with_error(mut self, error: Option<&'results str>) -> Self {
+        self.error = error;
+        self
+    }
+
+    /// Switches between the selected file and the diagnostics, when there are any
+    pub fn toggle_diagnostics(&mut self) {
+        self.show_diagnostics = !self.show_diagnostics && !self.diagnostics.is_empty();
+    }
+
+    /// Picks the suggestion at the given index, returns whether there was one
+    pub fn pick_suggestion(&mut self, index: usize) -> bool {
+        self.rerun = self.suggestions.get(index);
+        self.rerun.is_some()
+    }
+
+    /// Picks the selected result to find similar files of, returns whether there was one
+    pub fn pick_similar(&mut self) -> bool {
+        self.similar_to = self
+            .list_state
+            .selected()
+            .and_then(|index| self.items.get(index));
+        self.similar_to.is_some()
+    }
+
     pub fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.list_state.selected() {
             Some(i) => {
                 if i >= self.items.len() - 1 {
@@ -29,6 +94,9 @@ impl<'results> ResultsState<'results> {
     }
 
     pub fn previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.list_state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -43,17 +111,28 @@ impl<'results> ResultsState<'results> {
     }
 }
 
+#[cfg(test)]
+pub fn fake_results(contents: &[&str]) -> Vec<SearchResult> {
+    contents
+        .iter()
+        .enumerate()
+        .map(|(index, contents)| SearchResult {
+            source: std::path::PathBuf::from("source"),
+            path: std::path::PathBuf::from(format!("source/{}.txt", index)),
+            contents: contents.to_string(),
+            bytes: None,
+            matches: Vec::new(),
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn it_allows_to_move_forwards_in_the_results_list() {
-        let results = [
-            String::from("result0"),
-            String::from("result1"),
-            String::from("result2"),
-        ];
+        let results = fake_results(&["result0", "result1", "result2"]);
         let mut results_state = ResultsState::from_results(&results);
 
         assert_eq!(results_state.list_state.selected(), None);
@@ -73,11 +152,7 @@ mod tests {
 
     #[test]
     fn it_allows_to_move_backwards_in_the_results_list() {
-        let results = [
-            String::from("result0"),
-            String::from("result1"),
-            String::from("result2"),
-        ];
+        let results = fake_results(&["result0", "result1", "result2"]);
         let mut results_state = ResultsState::from_results(&results);
 
         assert_eq!(results_state.list_state.selected(), None);
@@ -94,4 +169,15 @@ mod tests {
         results_state.previous();
         assert_eq!(results_state.list_state.selected(), Some(0));
     }
+
+    #[test]
+    fn it_does_not_move_when_there_are_no_results() {
+        let results = fake_results(&[]);
+        let mut results_state = ResultsState::from_results(&results);
+
+        results_state.next();
+        results_state.previous();
+
+        assert_eq!(results_state.list_state.selected(), None);
+    }
 }
@@ -1,14 +1,18 @@
 #![allow(clippy::upper_case_acronyms)]
-use std::io::{self, Stdout};
+use std::{
+    collections::BTreeSet,
+    io::{self, Stdout},
+};
 
 use anyhow::Result;
 use crossterm::terminal::enable_raw_mode;
+use search::{CandidateKind, Diagnostic, Limit, SearchResult, Suggestion};
 use tui::{
     backend::{Backend, CrosstermBackend},
     layout::Layout,
     style::{Color, Modifier, Style},
     terminal::CompletedFrame,
-    text::{Span, Text},
+    text::{Span, Spans, Text},
     widgets::{Block, Borders, List, ListItem, Paragraph},
 };
 use tui::{
@@ -16,7 +20,11 @@ use tui::{
     Terminal,
 };
 
-use crate::ResultsState;
+use crate::{PromptState, ResultsState};
+
+const HEXDUMP_WIDTH: usize = 16;
+const HEXDUMP_CONTEXT_ROWS: usize = 2;
+const HEXDUMP_MAX_MATCHES: usize = 20;
 
 #[derive(Debug)]
 pub struct UI<B: Backend> {
@@ -56,8 +64,19 @@ where
                 })
                 .collect::<Vec<ListItem>>();
 
+            let mut title = String::from("Files (s: similar files");
+            if !results.diagnostics.is_empty() {
+                title.push_str(&format!(", d: {} diagnostics", results.diagnostics.len()));
+            }
+            title.push(')');
+            if !results.limits_hit.is_empty() {
+                title.push_str(&format!(
+                    ", stopped early by: {}",
+                    Self::limits(results.limits_hit)
+                ));
+            }
             let list = List::new(list_items)
-                .block(Block::default().title("Files").borders(Borders::ALL))
+                .block(Block::default().title(title).borders(Borders::ALL))
                 .highlight_style(
                     Style::default()
                         .bg(Color::LightGreen)
@@ -67,17 +86,257 @@ where
 
             f.render_stateful_widget(list, layout_chunks[1], &mut results.list_state);
 
-            let file_content_block = Block::default().title("File Content").borders(Borders::ALL);
-            let selected_file = results
-                .items
-                .get(results.list_state.selected().unwrap_or(0))
-                .unwrap();
-            let file_contents = Paragraph::new(Text::raw(selected_file)).block(file_content_block);
+            let file_content_title = match results.show_diagnostics {
+                true => "Diagnostics (d: back to the file)",
+                false => "File Content",
+            };
+            let file_content_block = Block::default()
+                .title(file_content_title)
+                .borders(Borders::ALL);
+            let selected_file = if results.show_diagnostics {
+                Self::diagnostics(results.diagnostics)
+            } else if let Some(error) = results.error {
+                Text::styled(error, Style::default().fg(Color::Red))
+            } else if results.items.is_empty() {
+                Self::suggestions(results.suggestions)
+            } else {
+                results
+                    .items
+                    .get(results.list_state.selected().unwrap_or(0))
+                    .map(|result| match &result.bytes {
+                        Some(bytes) => Self::hexdump(result, bytes),
+                        None => Self::highlight(result),
+                    })
+                    .unwrap_or_default()
+            };
+            let file_contents = Paragraph::new(selected_file).block(file_content_block);
             f.render_widget(file_contents, layout_chunks[0]);
         })?;
 
         Ok(frame)
     }
+
+    pub fn draw_prompt(&mut self, prompt: &mut PromptState) -> Result<CompletedFrame<'_>> {
+        let frame = self.terminal.draw(|f| {
+            let layout_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .margin(1)
+                .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+                .split(f.size());
+
+            let query = Paragraph::new(Spans::from(vec![
+                Span::raw(prompt.query.as_str()),
+                Span::styled(" ", Style::default().bg(Color::White)),
+            ]))
+            .block(Block::default().title("Search").borders(Borders::ALL));
+            f.render_widget(query, layout_chunks[0]);
+
+            let list_items = prompt
+                .candidates
+                .iter()
+                .map(|candidate| {
+                    let kind = match candidate.kind {
+                        CandidateKind::History => "history",
+                        CandidateKind::FileName => "file",
+                        CandidateKind::Term => "term",
+                    };
+                    ListItem::new(Spans::from(vec![
+                        Span::raw(candidate.text.as_str()),
+                        Span::styled(format!("  {}", kind), Style::default().fg(Color::DarkGray)),
+                    ]))
+                })
+                .collect::<Vec<ListItem>>();
+            let list = List::new(list_items)
+                .block(
+                    Block::default()
+                        .title("Tab to complete, Enter to search")
+                        .borders(Borders::ALL),
+                )
+                .highlight_style(
+                    Style::default()
+                        .bg(Color::LightGreen)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .highlight_symbol(">> ");
+            f.render_stateful_widget(list, layout_chunks[1], &mut prompt.list_state);
+        })?;
+
+        Ok(frame)
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        self.terminal.clear()?;
+        Ok(())
+    }
+
+    /// Rows of bytes around the first matches, each starting with its offset,
+    /// with the matched bytes highlighted in both the hex and the ASCII columns
+    fn hexdump<'result>(result: &SearchResult, bytes: &[u8]) -> Text<'result> {
+        let highlight_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+        let shown_matches = &result.matches[..result.matches.len().min(HEXDUMP_MAX_MATCHES)];
+        let last_row = bytes.len().saturating_sub(1) / HEXDUMP_WIDTH;
+        let rows = if shown_matches.is_empty() {
+            (0..=last_row.min(HEXDUMP_CONTEXT_ROWS * 2)).collect()
+        } else {
+            shown_matches
+                .iter()
+                .flat_map(|found| {
+                    let first = (found.start / HEXDUMP_WIDTH).saturating_sub(HEXDUMP_CONTEXT_ROWS);
+                    let last = (found.end.saturating_sub(1) / HEXDUMP_WIDTH + HEXDUMP_CONTEXT_ROWS)
+                        .min(last_row);
+                    first..=last
+                })
+                .collect::<BTreeSet<usize>>()
+        };
+
+        let offsets = shown_matches
+            .iter()
+            .map(|found| format!("0x{:08x}", found.start))
+            .collect::<Vec<String>>();
+        let mut lines = Vec::new();
+        if !offsets.is_empty() {
+            lines.push(Spans::from(format!("Matches at {}", offsets.join(", "))));
+        }
+        let mut previous_row = None;
+        for row in rows {
+            if matches!(previous_row, Some(previous_row) if previous_row + 1 != row) {
+                lines.push(Spans::from("..."));
+            }
+            previous_row = Some(row);
+
+            let row_start = row * HEXDUMP_WIDTH;
+            let row_bytes = &bytes[row_start..bytes.len().min(row_start + HEXDUMP_WIDTH)];
+            let style = |offset: usize| {
+                let is_matched = shown_matches
+                    .iter()
+                    .any(|found| found.start <= offset && offset < found.end);
+                if is_matched {
+                    highlight_style
+                } else {
+                    Style::default()
+                }
+            };
+
+            let mut spans = vec![Span::raw(format!("{:08x}  ", row_start))];
+            for (index, byte) in row_bytes.iter().enumerate() {
+                spans.push(Span::styled(
+                    format!("{:02x}", byte),
+                    style(row_start + index),
+                ));
+                spans.push(Span::raw(" "));
+            }
+            spans.push(Span::raw(
+                "   ".repeat(HEXDUMP_WIDTH - row_bytes.len()) + " |",
+            ));
+            for (index, byte) in row_bytes.iter().enumerate() {
+                let character = if byte.is_ascii_graphic() || *byte == b' ' {
+                    *byte as char
+                } else {
+                    '.'
+                };
+                spans.push(Span::styled(
+                    character.to_string(),
+                    style(row_start + index),
+                ));
+            }
+            spans.push(Span::raw("|"));
+            lines.push(Spans::from(spans));
+        }
+
+        Text::from(lines)
+    }
+
+    fn limits(limits_hit: &[Limit]) -> String {
+        limits_hit
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /// Every diagnostic of the search, one per line
+    fn diagnostics(diagnostics: &[Diagnostic]) -> Text<'_> {
+        let lines = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                Spans::from(vec![
+                    Span::styled(
+                        format!("{} ", diagnostic.kind),
+                        Style::default().fg(Color::Red),
+                    ),
+                    Span::raw(diagnostic.path.to_string_lossy()),
+                    Span::styled(
+                        format!(" {}", diagnostic.message),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ])
+            })
+            .collect::<Vec<Spans>>();
+
+        Text::from(lines)
+    }
+
+    /// What to show instead of an empty list, each suggestion picked with its number key
+    fn suggestions(suggestions: &[Suggestion]) -> Text<'_> {
+        if suggestions.is_empty() {
+            return Text::raw("No results found.");
+        }
+
+        let mut lines = vec![Spans::from("No results found. Did you mean:")];
+        lines.extend(suggestions.iter().enumerate().map(|(index, suggestion)| {
+            Spans::from(vec![
+                Span::styled(
+                    format!("[{}] ", index + 1),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(suggestion.term.as_str()),
+                Span::styled(
+                    format!(" ({} occurrences)", suggestion.frequency),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+        }));
+
+        Text::from(lines)
+    }
+
+    /// Styles every part of the contents covered by a match, including matches spanning several lines
+    fn highlight(result: &SearchResult) -> Text<'_> {
+        let highlight_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+        let contents = &result.contents;
+        let mut line_start = 0;
+
+        let lines = contents
+            .split('\n')
+            .map(|line| {
+                let line_end = line_start + line.len();
+                let mut spans = Vec::new();
+                let mut cursor = line_start;
+                for found in result
+                    .matches
+                    .iter()
+                    .filter(|found| found.start < line_end && found.end > line_start)
+                {
+                    let start = found.start.max(cursor);
+                    let end = found.end.min(line_end);
+                    if start > cursor {
+                        spans.push(Span::raw(&contents[cursor..start]));
+                    }
+                    if end > start {
+                        spans.push(Span::styled(&contents[start..end], highlight_style));
+                    }
+                    cursor = cursor.max(end);
+                }
+                if cursor < line_end {
+                    spans.push(Span::raw(&contents[cursor..line_end]));
+                }
+                line_start = line_end + 1;
+                Spans::from(spans)
+            })
+            .collect::<Vec<Spans>>();
+
+        Text::from(lines)
+    }
 }
 
 impl Default for UI<CrosstermBackend<Stdout>> {
@@ -96,17 +355,98 @@ mod tests {
     use tui::{backend::TestBackend, buffer::Buffer};
 
     use super::*;
+    use crate::results_state::fake_results;
+    use search::Match;
+
+    #[test]
+    fn it_highlights_matches_spanning_multiple_lines() {
+        let mut results = fake_results(&["a #[test]\nfn it() {}"]);
+        results[0].matches = vec![Match {
+            pattern: 0,
+            start: 2,
+            end: 15,
+            start_line: 1,
+            end_line: 2,
+        }];
+
+        let text = UI::<TestBackend>::highlight(&results[0]);
+
+        let highlight_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+        assert_eq!(
+            text,
+            Text::from(vec![
+                Spans::from(vec![
+                    Span::raw("a "),
+                    Span::styled("#[test]", highlight_style)
+                ]),
+                Spans::from(vec![
+                    Span::styled("fn it", highlight_style),
+                    Span::raw("() {}")
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn it_shows_a_hexdump_around_the_byte_matches() {
+        let mut results = fake_results(&[""]);
+        let bytes = (0..=255).collect::<Vec<u8>>();
+        results[0].matches = vec![Match {
+            pattern: 0,
+            start: 0x80,
+            end: 0x82,
+            start_line: 1,
+            end_line: 1,
+        }];
+
+        let text = UI::<TestBackend>::hexdump(&results[0], &bytes);
+
+        let lines = text
+            .lines
+            .iter()
+            .map(|spans| {
+                spans
+                    .0
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "Matches at 0x00000080");
+        assert!(lines[1].starts_with("00000060  60 61 62"));
+        assert!(lines[3].ends_with("|................|"));
+        assert_eq!(
+            text.lines[3].0[1],
+            Span::styled("80", Style::default().fg(Color::Black).bg(Color::Yellow))
+        );
+    }
+
+    #[test]
+    fn it_lists_the_suggestions_with_their_keys() {
+        let suggestions = vec![Suggestion {
+            pattern: 0,
+            term: "timeout".to_string(),
+            distance: 1,
+            frequency: 3,
+        }];
+
+        let text = UI::<TestBackend>::suggestions(&suggestions);
+
+        assert_eq!(text.lines.len(), 2);
+        assert_eq!(text.lines[1].0[1], Span::raw("timeout"));
+        assert_eq!(
+            UI::<TestBackend>::suggestions(&[]),
+            Text::raw("No results found.")
+        );
+    }
 
     #[test]
     #[ignore]
     fn it_draws() -> Result<()> {
         let test_backend = TestBackend::new(10, 10);
         let mut ui = UI::new(test_backend);
-        let results = [
-            String::from("test0"),
-            String::from("test1"),
-            String::from("test2"),
-        ];
+        let results = fake_results(&["test0", "test1", "test2"]);
         let mut results_state = ResultsState::from_results(&results);
 
         let frame = ui.draw(&mut results_state)?;
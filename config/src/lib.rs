@@ -3,20 +3,55 @@ use core::panic;
 use home::home_dir;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     fs::OpenOptions,
     io::Read,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ConfigMap {
     pub sources: Vec<PathBuf>,
+    /// Language used to analyze prose sources, such as notes and docs, by source path
+    #[serde(default)]
+    pub analyzers: BTreeMap<PathBuf, Language>,
+    /// Past search queries, oldest first
+    #[serde(default)]
+    pub history: Vec<String>,
+    /// Threads searches run on, one per CPU when it isn't set
+    #[serde(default)]
+    pub threads: Option<usize>,
 }
 
 impl Default for ConfigMap {
     fn default() -> Self {
         Self {
             sources: Vec::new(),
+            analyzers: BTreeMap::new(),
+            history: Vec::new(),
+            threads: None,
+        }
+    }
+}
+
+const MAX_HISTORY_SIZE: usize = 100;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    English,
+    Portuguese,
+}
+
+impl FromStr for Language {
+    type Err = anyhow::Error;
+
+    fn from_str(language: &str) -> Result<Self, Self::Err> {
+        match language {
+            "english" => Ok(Language::English),
+            "portuguese" => Ok(Language::Portuguese),
+            _ => Err(anyhow!("Unsupported language: {}", language)),
         }
     }
 }
@@ -26,6 +61,10 @@ pub trait Configuration {
     fn add_source<P: AsRef<Path>>(&mut self, path: P) -> Result<()>;
     fn delete_source<P: AsRef<Path>>(&mut self, path: P) -> Result<()>;
     fn get_sources(&self) -> &Vec<PathBuf>;
+    fn set_analyzer<P: AsRef<Path>>(&mut self, path: P, language: Option<Language>) -> Result<()>;
+    fn get_analyzer<P: AsRef<Path>>(&self, path: P) -> Option<Language>;
+    fn add_to_history(&mut self, query: &str);
+    fn get_history(&self) -> &Vec<String>;
 }
 
 #[derive(Debug)]
@@ -96,6 +135,7 @@ impl Configuration for Config {
         if let Some(index) = index {
             self.config_map.sources.remove(index);
         }
+        self.config_map.analyzers.remove(path.as_ref());
 
         Ok(())
     }
@@ -103,6 +143,45 @@ impl Configuration for Config {
     fn get_sources(&self) -> &Vec<PathBuf> {
         &self.config_map.sources
     }
+
+    fn set_analyzer<P: AsRef<Path>>(&mut self, path: P, language: Option<Language>) -> Result<()> {
+        let source_exists = self
+            .config_map
+            .sources
+            .iter()
+            .any(|source_path| source_path == path.as_ref());
+
+        if !source_exists {
+            return Err(anyhow!("Source does not exist"));
+        }
+
+        match language {
+            Some(language) => self
+                .config_map
+                .analyzers
+                .insert(path.as_ref().into(), language),
+            None => self.config_map.analyzers.remove(path.as_ref()),
+        };
+
+        Ok(())
+    }
+
+    fn get_analyzer<P: AsRef<Path>>(&self, path: P) -> Option<Language> {
+        self.config_map.analyzers.get(path.as_ref()).copied()
+    }
+
+    fn add_to_history(&mut self, query: &str) {
+        let history = &mut self.config_map.history;
+        history.retain(|past_query| past_query != query);
+        history.push(query.to_string());
+        if history.len() > MAX_HISTORY_SIZE {
+            history.drain(..history.len() - MAX_HISTORY_SIZE);
+        }
+    }
+
+    fn get_history(&self) -> &Vec<String> {
+        &self.config_map.history
+    }
 }
 
 impl Default for Config {
@@ -219,6 +298,87 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn it_sets_and_removes_the_analyzer_of_a_source() -> Result<()> {
+        let dir = tempdir()?;
+        let source_path = dir.path().to_path_buf();
+        let mut config = Config::load_or_create(dir.path().to_path_buf())?;
+        config.add_source(&source_path)?;
+
+        config.set_analyzer(&source_path, Some(Language::Portuguese))?;
+        assert_eq!(
+            config.get_analyzer(&source_path),
+            Some(Language::Portuguese)
+        );
+
+        config.set_analyzer(&source_path, None)?;
+        assert_eq!(config.get_analyzer(&source_path), None);
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "Source does not exist")]
+    fn it_errors_when_setting_the_analyzer_of_an_unknown_source() {
+        let dir = tempdir().unwrap();
+        let mut config = Config::load_or_create(dir.path().to_path_buf()).unwrap();
+
+        config
+            .set_analyzer("fake_source", Some(Language::English))
+            .unwrap();
+    }
+
+    #[test]
+    fn it_loads_the_analyzers_of_the_configuration() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join(Config::DEFAULT_CONFIG_NAME);
+        let mut file = File::create(file_path)?;
+        writeln!(
+            file,
+            r#"{{
+            "sources": ["fake_source"],
+            "analyzers": {{ "fake_source": "english" }}
+        }}"#
+        )?;
+
+        let config = Config::load_or_create(dir.into_path())?;
+
+        assert_eq!(config.get_analyzer("fake_source"), Some(Language::English));
+        assert_eq!(config.get_analyzer("other_source"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn it_loads_the_threads_of_the_configuration() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join(Config::DEFAULT_CONFIG_NAME);
+        let mut file = File::create(file_path)?;
+        writeln!(file, r#"{{ "sources": [], "threads": 4 }}"#)?;
+
+        let config = Config::load_or_create(dir.into_path())?;
+
+        assert_eq!(config.config_map.threads, Some(4));
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_the_most_recent_queries_once() -> Result<()> {
+        let dir = tempdir()?;
+        let mut config = Config::load_or_create(dir.path().to_path_buf())?;
+
+        for query in 0..MAX_HISTORY_SIZE {
+            config.add_to_history(&query.to_string());
+        }
+        config.add_to_history("timeout");
+        config.add_to_history("1");
+
+        let history = config.get_history();
+        assert_eq!(history.len(), MAX_HISTORY_SIZE);
+        assert_eq!(history[0], "2");
+        assert_eq!(history[MAX_HISTORY_SIZE - 2..], ["timeout", "1"]);
+        Ok(())
+    }
+
     #[test]
     fn it_removes_a_source_from_the_list() -> Result<()> {
         let dir = tempdir()?;
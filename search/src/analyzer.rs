@@ -0,0 +1,106 @@
+use std::fmt;
+
+use config::Language;
+use rust_stemmers::{Algorithm, Stemmer};
+
+use crate::tokenizer::{tokenize, Token};
+
+const ENGLISH_STOP_WORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "been",
+    "before", "being", "but", "by", "can", "could", "did", "do", "does", "for", "from", "had",
+    "has", "have", "he", "her", "his", "how", "i", "if", "in", "into", "is", "it", "its", "just",
+    "may", "more", "most", "no", "not", "of", "on", "only", "or", "other", "our", "out", "over",
+    "she", "should", "so", "some", "such", "than", "that", "the", "their", "them", "then", "there",
+    "these", "they", "this", "those", "to", "under", "up", "was", "we", "were", "what", "when",
+    "where", "which", "while", "who", "will", "with", "would", "you", "your",
+];
+
+const PORTUGUESE_STOP_WORDS: &[&str] = &[
+    "a", "ao", "aos", "as", "até", "com", "como", "da", "das", "de", "dela", "dele", "do", "dos",
+    "e", "ela", "ele", "eles", "em", "entre", "era", "essa", "esse", "esta", "este", "eu", "foi",
+    "for", "há", "isso", "isto", "já", "lhe", "mais", "mas", "me", "mesmo", "meu", "minha",
+    "muito", "na", "nas", "no", "nos", "não", "nós", "o", "os", "ou", "para", "pela", "pelo",
+    "por", "quando", "que", "se", "sem", "ser", "seu", "sua", "são", "também", "te", "tem", "um",
+    "uma", "você", "à", "às", "é",
+];
+
+/// Reduces prose to the words that carry meaning, stemmed so that "deploying",
+/// "deployed" and "deploy" all end up as the same term
+pub struct Analyzer {
+    language: Language,
+    stemmer: Stemmer,
+}
+
+impl Analyzer {
+    pub fn new(language: Language) -> Self {
+        let algorithm = match language {
+            Language::English => Algorithm::English,
+            Language::Portuguese => Algorithm::Portuguese,
+        };
+
+        Self {
+            language,
+            stemmer: Stemmer::create(algorithm),
+        }
+    }
+
+    /// Tokens of the text without stop words, each term replaced by its stem.
+    /// Offsets still point at the original words.
+    pub fn analyze(&self, text: &str) -> Vec<Token> {
+        let stop_words = match self.language {
+            Language::English => ENGLISH_STOP_WORDS,
+            Language::Portuguese => PORTUGUESE_STOP_WORDS,
+        };
+
+        tokenize(text)
+            .into_iter()
+            .filter(|token| !stop_words.contains(&token.term.as_str()))
+            .map(|token| Token {
+                term: self.stemmer.stem(&token.term).into_owned(),
+                ..token
+            })
+            .collect()
+    }
+
+    pub fn terms(&self, text: &str) -> Vec<String> {
+        self.analyze(text)
+            .into_iter()
+            .map(|token| token.term)
+            .collect()
+    }
+}
+
+impl fmt::Debug for Analyzer {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Analyzer")
+            .field("language", &self.language)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_stems_english_words() {
+        let analyzer = Analyzer::new(Language::English);
+
+        assert_eq!(
+            analyzer.terms("Deploying deployed deploy"),
+            vec!["deploy", "deploy", "deploy"]
+        );
+    }
+
+    #[test]
+    fn it_drops_stop_words() {
+        let analyzer = Analyzer::new(Language::Portuguese);
+
+        let tokens = analyzer.analyze("a configuração do serviço");
+
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].start, 2);
+        assert_eq!(tokens[0].term, analyzer.terms("configurações")[0]);
+    }
+}
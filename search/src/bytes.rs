@@ -0,0 +1,160 @@
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use anyhow::{anyhow, Result};
+
+use crate::{Match, MatchMode};
+
+/// Parses hex digits such as `de ad be ef` or `deadbeef` into bytes
+pub fn parse_hex(hex: &str) -> Result<Vec<u8>> {
+    let digits = hex
+        .chars()
+        .filter(|character| !character.is_whitespace())
+        .collect::<Vec<char>>();
+    if digits.is_empty() || digits.len() % 2 != 0 {
+        return Err(anyhow!(
+            "Invalid hex pattern: {}, it needs pairs of digits",
+            hex
+        ));
+    }
+
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair = pair.iter().collect::<String>();
+            u8::from_str_radix(&pair, 16)
+                .map_err(|_| anyhow!("Invalid hex pattern: {}, {} isn't a byte", hex, pair))
+        })
+        .collect()
+}
+
+/// Parses a string where bytes can be escaped like `\x7fELF`. Also understands
+/// `\n`, `\r`, `\t`, `\0` and `\\`, everything else stands for its UTF-8 bytes.
+pub fn parse_escaped(escaped: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(escaped.len());
+    let mut characters = escaped.chars();
+
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(character.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        let byte = match characters.next() {
+            Some('x') => {
+                let digits = characters.by_ref().take(2).collect::<String>();
+                u8::from_str_radix(&digits, 16).map_err(|_| {
+                    anyhow!(
+                        "Invalid escaped pattern: {}, \\x{} isn't a byte",
+                        escaped,
+                        digits
+                    )
+                })?
+            }
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some('0') => b'\0',
+            Some('\\') => b'\\',
+            Some(other) => {
+                return Err(anyhow!(
+                    "Invalid escaped pattern: {}, unknown escape \\{}",
+                    escaped,
+                    other
+                ))
+            }
+            None => {
+                return Err(anyhow!(
+                    "Invalid escaped pattern: {}, it ends in \\",
+                    escaped
+                ))
+            }
+        };
+        bytes.push(byte);
+    }
+
+    Ok(bytes)
+}
+
+/// Finds byte sequences in files of any kind, text or not
+#[derive(Debug)]
+pub(crate) struct ByteMatcher {
+    automaton: AhoCorasick,
+}
+
+impl ByteMatcher {
+    pub(crate) fn new(patterns: &[Vec<u8>]) -> Self {
+        Self {
+            automaton: AhoCorasickBuilder::new()
+                .match_kind(MatchKind::LeftmostLongest)
+                .build(patterns),
+        }
+    }
+
+    /// Matches of the file, if it should be in the results. Inverted matching
+    /// works on lines, so it's rejected before searching for bytes.
+    pub(crate) fn apply(&self, mode: &MatchMode, contents: &[u8]) -> Option<Vec<Match>> {
+        match mode {
+            MatchMode::Matching => Some(self.find(contents)).filter(|matches| !matches.is_empty()),
+            MatchMode::FilesWithoutMatch => {
+                Some(Vec::new()).filter(|_| !self.automaton.is_match(contents))
+            }
+            MatchMode::Inverted => None,
+        }
+    }
+
+    fn find(&self, contents: &[u8]) -> Vec<Match> {
+        let mut line = 1;
+        let mut line_counted_until = 0;
+        self.automaton
+            .find_iter(contents)
+            .map(|found| {
+                let newlines = |bytes: &[u8]| bytes.iter().filter(|byte| **byte == b'\n').count();
+                line += newlines(&contents[line_counted_until..found.start()]);
+                line_counted_until = found.start();
+                Match {
+                    pattern: found.pattern(),
+                    start: found.start(),
+                    end: found.end(),
+                    start_line: line,
+                    end_line: line + newlines(&contents[found.start()..found.end()]),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_hex_and_escaped_patterns() -> Result<()> {
+        assert_eq!(parse_hex("de ad BE EF")?, vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(parse_hex("7f454c46")?, b"\x7fELF".to_vec());
+        assert!(parse_hex("dea").is_err());
+        assert!(parse_hex("zz").is_err());
+
+        assert_eq!(parse_escaped("\\x7fELF\\n")?, b"\x7fELF\n".to_vec());
+        assert_eq!(parse_escaped("ç\\\\")?, "ç\\".as_bytes().to_vec());
+        assert!(parse_escaped("\\xg0").is_err());
+        assert!(parse_escaped("\\q").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_finds_byte_patterns_in_binary_contents() {
+        let matcher = ByteMatcher::new(&[vec![0xde, 0xad], b"ELF".to_vec()]);
+        let contents = [0x7f, b'E', b'L', b'F', 0xff, b'\n', 0xde, 0xad, 0x00];
+
+        let matches = matcher.apply(&MatchMode::Matching, &contents).unwrap();
+
+        let found = matches
+            .iter()
+            .map(|found| (found.pattern, found.start, found.end, found.start_line))
+            .collect::<Vec<(usize, usize, usize, usize)>>();
+        assert_eq!(found, vec![(1, 1, 4, 1), (0, 6, 8, 2)]);
+        assert_eq!(
+            matcher.apply(&MatchMode::FilesWithoutMatch, &contents),
+            None
+        );
+    }
+}
@@ -0,0 +1,528 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    fmt::Debug,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use config::{Config, Configuration};
+use home::home_dir;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    git::{self, Revision},
+    limits::{Budget, Limits},
+    walker::Walker,
+    Diagnostic, DiagnosticKind, Match, MatchMode, Search, SearchOptions, SearchResult,
+    SearchResults, SearchStats, Suggestion, Vocabulary,
+};
+
+/// Entries beyond the most recently used ones are removed
+const MAX_ENTRIES: usize = 256;
+const MAX_ENTRY_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Where a query matched, along with the state of the sources it was found in.
+/// Contents aren't kept, they're read again from the files.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    fingerprints: Vec<String>,
+    results: Vec<CachedResult>,
+    stats: SearchStats,
+    suggestions: Vec<Suggestion>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Vocabulary of the sources, along with their state when it was built
+#[derive(Serialize, Deserialize)]
+struct VocabularyEntry {
+    fingerprints: Vec<String>,
+    vocabulary: Vocabulary,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedResult {
+    source: PathBuf,
+    path: PathBuf,
+    /// Hash of the contents searched, the matches only hold for them
+    digest: String,
+    matches: Vec<Match>,
+}
+
+/// Keeps the matches of each query on disk, so repeating it doesn't rescan
+/// sources that haven't changed since. Each query has a single entry, which is
+/// replaced as soon as any of its sources changes. Entries unused for a month,
+/// or beyond the most recently used ones, are removed.
+#[derive(Debug)]
+pub struct ResultCache {
+    directory: PathBuf,
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        let home_dir = match home_dir() {
+            Some(home_dir) => home_dir,
+            None => panic!("Unable to determine home dir location"),
+        };
+        Self::new(home_dir.join(".wicli").join("cache"))
+    }
+}
+
+impl ResultCache {
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+
+    pub fn search(&self, config: &Config, options: &SearchOptions) -> Result<SearchResults> {
+        let fingerprints =
+            match Self::fingerprints(config, options.git_ref.as_deref(), options.limits) {
+                Some(fingerprints) if Self::is_cacheable(options) => fingerprints,
+                _ => return Search::with_options(config, options),
+            };
+        let entry_path = self.entry_path(config, options);
+        if let Some(entry) = Self::read::<CacheEntry>(&entry_path) {
+            if entry.fingerprints == fingerprints {
+                if let Some(search_results) = Self::load(entry, options) {
+                    Self::touch(&entry_path);
+                    return Ok(search_results);
+                }
+            }
+        }
+
+        let mut search_results = Search::with_options(config, options)?;
+        // Results cut short by a limit like the timeout could be complete next time
+        if !search_results.stats.limits_hit.is_empty() {
+            return Ok(search_results);
+        }
+        let entry = CacheEntry {
+            fingerprints,
+            results: search_results
+                .results
+                .iter()
+                .map(|result| CachedResult {
+                    source: result.source.clone(),
+                    path: result.path.clone(),
+                    digest: Self::digest(
+                        result
+                            .bytes
+                            .as_deref()
+                            .unwrap_or(result.contents.as_bytes()),
+                    ),
+                    matches: result.matches.clone(),
+                })
+                .collect(),
+            stats: search_results.stats.clone(),
+            suggestions: search_results.suggestions.clone(),
+            diagnostics: search_results.diagnostics.clone(),
+        };
+        if let Err(error) = self.write(&entry_path, &entry) {
+            search_results.diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::CacheError,
+                path: entry_path,
+                message: error.to_string(),
+            });
+        }
+
+        Ok(search_results)
+    }
+
+    /// Words and file names of the sources to complete queries with, built
+    /// again only once a source changes
+    pub fn vocabulary(&self, config: &Config, git_ref: Option<&str>) -> Vocabulary {
+        let fingerprints = match Self::fingerprints(config, git_ref, Limits::default()) {
+            Some(fingerprints) => fingerprints,
+            None => return Vocabulary::build(config, git_ref),
+        };
+        let entry_path = self.entry_path(config, ("vocabulary", git_ref));
+        if let Some(entry) = Self::read::<VocabularyEntry>(&entry_path) {
+            if entry.fingerprints == fingerprints {
+                Self::touch(&entry_path);
+                return entry.vocabulary;
+            }
+        }
+
+        let entry = VocabularyEntry {
+            fingerprints,
+            vocabulary: Vocabulary::build(config, git_ref),
+        };
+        // Completions print nothing but candidates, the vocabulary is built again next time
+        let _ = self.write(&entry_path, &entry);
+
+        entry.vocabulary
+    }
+
+    /// Kept as recently used, so it's the last to be evicted
+    fn touch(entry_path: &Path) {
+        let _ = File::options()
+            .write(true)
+            .open(entry_path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+    }
+
+    /// Only the results showing the whole contents of their files can be read
+    /// back from them, not the ones showing lines, entries or documents
+    fn is_cacheable(options: &SearchOptions) -> bool {
+        options.structured.is_none() && options.log.is_none() && options.mode != MatchMode::Inverted
+    }
+
+    /// Results of the entry with the contents of their files, `None` when any
+    /// of them can't be read anymore or changed without the source telling
+    fn load(entry: CacheEntry, options: &SearchOptions) -> Option<SearchResults> {
+        let contents = Self::read_contents(&entry.results, options.git_ref.as_deref())?;
+        let results = entry
+            .results
+            .into_iter()
+            .zip(contents)
+            .map(|(cached, contents)| {
+                if Self::digest(&contents) != cached.digest {
+                    return None;
+                }
+                let (contents, bytes) = match options.byte_patterns.is_empty() {
+                    true => (String::from_utf8(contents).ok()?, None),
+                    false => (String::new(), Some(contents)),
+                };
+                Some(SearchResult {
+                    source: cached.source,
+                    path: cached.path,
+                    contents,
+                    bytes,
+                    matches: cached.matches,
+                })
+            })
+            .collect::<Option<Vec<SearchResult>>>()?;
+
+        Some(SearchResults {
+            results,
+            stats: entry.stats,
+            suggestions: entry.suggestions,
+            diagnostics: entry.diagnostics,
+        })
+    }
+
+    /// Contents of the files of the results, from the working tree or from the
+    /// revision searched, in the same order
+    fn read_contents(results: &[CachedResult], git_ref: Option<&str>) -> Option<Vec<Vec<u8>>> {
+        let git_ref = match git_ref {
+            Some(git_ref) => git_ref,
+            None => {
+                return results
+                    .iter()
+                    .map(|result| fs::read(&result.path).ok())
+                    .collect()
+            }
+        };
+
+        let mut by_source = BTreeMap::<&Path, Vec<usize>>::new();
+        for (index, result) in results.iter().enumerate() {
+            by_source.entry(&result.source).or_default().push(index);
+        }
+        let mut contents = vec![None; results.len()];
+        for (source_path, indexes) in by_source {
+            let revision = Revision::resolve(source_path, git_ref).ok()?;
+            let paths = indexes
+                .iter()
+                .map(|index| Some(results[*index].path.strip_prefix(source_path).ok()?.into()))
+                .collect::<Option<Vec<PathBuf>>>()?;
+            for (index, (_, blob)) in indexes.iter().zip(revision.read(paths).ok()?) {
+                contents[*index] = Some(blob.ok()?);
+            }
+        }
+
+        contents.into_iter().collect()
+    }
+
+    /// Queries are told apart by their options, the sources they run on and
+    /// the analyzer of each source. The crate version is part of it, so results
+    /// cached by an older wicli aren't read back.
+    fn entry_path<K: Debug>(&self, config: &Config, options: K) -> PathBuf {
+        let sources = config
+            .config_map
+            .sources
+            .iter()
+            .map(|source_path| (source_path, config.get_analyzer(source_path)))
+            .collect::<Vec<_>>();
+        let key = format!(
+            "{}\n{:?}\n{:?}",
+            env!("CARGO_PKG_VERSION"),
+            options,
+            sources
+        );
+
+        self.directory
+            .join(format!("{}.json", blake3::hash(key.as_bytes()).to_hex()))
+    }
+
+    /// State of every source, or `None` when one of them can't be told within
+    /// the limits of the query, so it runs without the cache
+    fn fingerprints(config: &Config, git_ref: Option<&str>, limits: Limits) -> Option<Vec<String>> {
+        let budget = Budget::new(limits);
+        config
+            .config_map
+            .sources
+            .iter()
+            .map(|source_path| Self::fingerprint(source_path, git_ref, &budget))
+            .collect()
+    }
+
+    /// The commit searched in git sources, plus the latest change to the working
+    /// tree when that's what is searched
+    fn fingerprint(source_path: &Path, git_ref: Option<&str>, budget: &Budget) -> Option<String> {
+        let is_repository = git::is_repository(source_path);
+        if let (Some(git_ref), true) = (git_ref, is_repository) {
+            let revision = Revision::resolve(source_path, git_ref).ok()?;
+            return Some(revision.commit().to_string());
+        }
+        let last_modified = Walker::last_modified(source_path, budget)?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_nanos();
+
+        Some(match git::head(source_path) {
+            Some(head) if is_repository => format!("{}:{}", head, last_modified),
+            _ => last_modified.to_string(),
+        })
+    }
+
+    fn digest(contents: &[u8]) -> String {
+        blake3::hash(contents).to_hex().to_string()
+    }
+
+    fn read<T: DeserializeOwned>(entry_path: &Path) -> Option<T> {
+        let contents = fs::read(entry_path).ok()?;
+        serde_json::from_slice(&contents).ok()
+    }
+
+    fn write<T: Serialize>(&self, entry_path: &Path, entry: &T) -> Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        fs::write(entry_path, serde_json::to_vec(entry)?)?;
+        self.evict()?;
+
+        Ok(())
+    }
+
+    /// Removes the entries unused for too long, and the least recently used
+    /// ones once there are too many
+    fn evict(&self) -> Result<()> {
+        let mut entries = fs::read_dir(&self.directory)?
+            .filter_map(|dir_entry| {
+                let path = dir_entry.ok()?.path();
+                let modified = fs::metadata(&path).ok()?.modified().ok()?;
+                Some((modified, path)).filter(|(_, path)| {
+                    path.extension().and_then(|extension| extension.to_str()) == Some("json")
+                })
+            })
+            .collect::<Vec<(SystemTime, PathBuf)>>();
+        entries.sort_by_key(|(modified, _)| Reverse(*modified));
+
+        for (index, (modified, path)) in entries.into_iter().enumerate() {
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+            if index >= MAX_ENTRIES || age > MAX_ENTRY_AGE {
+                fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Limit;
+    use tempfile::tempdir;
+
+    fn contents(search_results: &SearchResults) -> Vec<&str> {
+        search_results
+            .results
+            .iter()
+            .map(|result| result.contents.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn it_reuses_the_results_until_a_source_changes() -> Result<()> {
+        let source = tempdir()?;
+        let file_path = source.path().join("notes.txt");
+        fs::write(&file_path, "cached term")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let cache_dir = tempdir()?;
+        let cache = ResultCache::new(cache_dir.path().to_path_buf());
+        let options = SearchOptions {
+            patterns: vec![String::from("term")],
+            ..SearchOptions::default()
+        };
+
+        let first = cache.search(&config, &options)?;
+        assert_eq!(contents(&first), vec!["cached term"]);
+        assert_eq!(fs::read_dir(cache_dir.path())?.count(), 1);
+
+        let cached = cache.search(&config, &options)?;
+        assert_eq!(contents(&cached), vec!["cached term"]);
+        assert_eq!(cached.results[0].matches[0].start, 7);
+        // Statistics of the run the results were cached from
+        assert_eq!(cached.stats.elapsed, first.stats.elapsed);
+
+        fs::write(&file_path, "changed term")?;
+        let rescanned = cache.search(&config, &options)?;
+        assert_eq!(contents(&rescanned), vec!["changed term"]);
+        assert_eq!(rescanned.results[0].matches[0].start, 8);
+        assert_eq!(fs::read_dir(cache_dir.path())?.count(), 1);
+        assert!(
+            !fs::read_to_string(fs::read_dir(cache_dir.path())?.next().unwrap()?.path())?
+                .contains("changed term")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_searches_again_when_a_file_changed_without_its_source_telling() -> Result<()> {
+        let source = tempdir()?;
+        let file_path = source.path().join("notes.txt");
+        fs::write(&file_path, "cached term")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let cache_dir = tempdir()?;
+        let cache = ResultCache::new(cache_dir.path().to_path_buf());
+        let options = SearchOptions {
+            patterns: vec![String::from("term")],
+            ..SearchOptions::default()
+        };
+        cache.search(&config, &options)?;
+
+        // Same modification time, the source looks unchanged
+        let modified = fs::metadata(&file_path)?.modified()?;
+        fs::write(&file_path, "changed term")?;
+        File::options()
+            .write(true)
+            .open(&file_path)?
+            .set_modified(modified)?;
+        let search_results = cache.search(&config, &options)?;
+
+        assert_eq!(contents(&search_results), vec!["changed term"]);
+        assert_eq!(search_results.results[0].matches[0].start, 8);
+        Ok(())
+    }
+
+    #[test]
+    fn it_does_not_keep_the_results_of_searches_cut_short() -> Result<()> {
+        let source = tempdir()?;
+        fs::write(source.path().join("first.txt"), "term")?;
+        fs::write(source.path().join("second.txt"), "term")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let cache_dir = tempdir()?;
+        let cache = ResultCache::new(cache_dir.path().to_path_buf());
+        let options = SearchOptions {
+            patterns: vec![String::from("term")],
+            limits: Limits {
+                max_results: Some(1),
+                ..Limits::default()
+            },
+            ..SearchOptions::default()
+        };
+
+        let search_results = cache.search(&config, &options)?;
+
+        assert_eq!(search_results.stats.limits_hit, vec![Limit::MaxResults]);
+        assert_eq!(fs::read_dir(cache_dir.path())?.count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn it_gives_up_on_the_fingerprint_once_the_time_is_spent() -> Result<()> {
+        let source = tempdir()?;
+        fs::write(source.path().join("notes.txt"), "term")?;
+        let spent = Budget::new(Limits {
+            timeout: Some(Duration::ZERO),
+            ..Limits::default()
+        });
+
+        assert!(ResultCache::fingerprint(source.path(), None, &spent).is_none());
+        assert!(
+            ResultCache::fingerprint(source.path(), None, &Budget::new(Limits::default()))
+                .is_some()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_reuses_the_vocabulary_until_a_source_changes() -> Result<()> {
+        let source = tempdir()?;
+        let file_path = source.path().join("notes.txt");
+        fs::write(&file_path, "timeout")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let cache_dir = tempdir()?;
+        let cache = ResultCache::new(cache_dir.path().to_path_buf());
+
+        let vocabulary = cache.vocabulary(&config, None);
+        assert_eq!(vocabulary.words_starting_with("time"), vec!["timeout"]);
+
+        let modified = fs::metadata(&file_path)?.modified()?;
+        fs::write(&file_path, "timer")?;
+        File::options()
+            .write(true)
+            .open(&file_path)?
+            .set_modified(modified)?;
+        let cached = cache.vocabulary(&config, None);
+        assert_eq!(cached.words_starting_with("time"), vec!["timeout"]);
+
+        File::options()
+            .write(true)
+            .open(&file_path)?
+            .set_modified(modified + Duration::from_secs(1))?;
+        let rebuilt = cache.vocabulary(&config, None);
+        assert_eq!(rebuilt.words_starting_with("time"), vec!["timer"]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_evicts_old_and_least_recently_used_entries() -> Result<()> {
+        let source = tempdir()?;
+        fs::write(source.path().join("notes.txt"), "cached term")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let cache_dir = tempdir()?;
+        let cache = ResultCache::new(cache_dir.path().to_path_buf());
+        let now = SystemTime::now();
+        let old_entry = cache_dir.path().join("old.json");
+        fs::write(&old_entry, "{}")?;
+        File::options()
+            .write(true)
+            .open(&old_entry)?
+            .set_modified(now - MAX_ENTRY_AGE - Duration::from_secs(1))?;
+        for index in 0..MAX_ENTRIES {
+            let entry = cache_dir.path().join(format!("{}.json", index));
+            fs::write(&entry, "{}")?;
+            File::options()
+                .write(true)
+                .open(&entry)?
+                .set_modified(now - Duration::from_secs(index as u64 + 1))?;
+        }
+        let options = SearchOptions {
+            patterns: vec![String::from("term")],
+            ..SearchOptions::default()
+        };
+
+        cache.search(&config, &options)?;
+
+        assert_eq!(fs::read_dir(cache_dir.path())?.count(), MAX_ENTRIES);
+        assert!(!old_entry.exists());
+        assert!(!cache_dir
+            .path()
+            .join(format!("{}.json", MAX_ENTRIES - 1))
+            .exists());
+        assert!(cache_dir.path().join("0.json").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use crate::Vocabulary;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CandidateKind {
+    History,
+    FileName,
+    Term,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// The whole query once completed
+    pub text: String,
+    pub kind: CandidateKind,
+}
+
+/// Completes a query with past queries starting like it, then completes its
+/// last word with file names and words found in the sources
+#[derive(Debug, Default)]
+pub struct Completer {
+    history: Vec<String>,
+    vocabulary: Vocabulary,
+}
+
+impl Completer {
+    /// The history goes from the oldest to the most recent query
+    pub fn new(history: Vec<String>, vocabulary: Vocabulary) -> Self {
+        Self {
+            history,
+            vocabulary,
+        }
+    }
+
+    pub fn complete(&self, query: &str, limit: usize) -> Vec<Candidate> {
+        let lowercase_query = query.to_lowercase();
+        let last_word = query.rsplit(char::is_whitespace).next().unwrap_or_default();
+        let query_start = &query[..query.len() - last_word.len()];
+
+        let past_queries = self
+            .history
+            .iter()
+            .rev()
+            .filter(|past_query| past_query.to_lowercase().starts_with(&lowercase_query))
+            .map(|past_query| (past_query.to_string(), CandidateKind::History));
+        let completed_words = match last_word {
+            "" => Vec::new(),
+            last_word => self
+                .vocabulary
+                .file_names_starting_with(last_word)
+                .into_iter()
+                .map(|file_name| (file_name, CandidateKind::FileName))
+                .chain(
+                    self.vocabulary
+                        .words_starting_with(last_word)
+                        .into_iter()
+                        .map(|word| (word, CandidateKind::Term)),
+                )
+                .map(|(word, kind)| (format!("{}{}", query_start, word), kind))
+                .collect(),
+        };
+
+        let mut candidates: Vec<Candidate> = Vec::new();
+        for (text, kind) in past_queries.chain(completed_words) {
+            if candidates.len() == limit {
+                break;
+            }
+            if text != query && !candidates.iter().any(|candidate| candidate.text == text) {
+                candidates.push(Candidate { text, kind });
+            }
+        }
+
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn completer() -> Completer {
+        let mut vocabulary = Vocabulary::default();
+        vocabulary.add_file(
+            Path::new("source/deploy.md"),
+            "deployment deployed deployed",
+        );
+        Completer::new(
+            vec!["deploy service".to_string(), "retries".to_string()],
+            vocabulary,
+        )
+    }
+
+    #[test]
+    fn it_completes_with_history_then_file_names_then_terms() {
+        let candidates = completer().complete("dep", 10);
+
+        assert_eq!(
+            candidates,
+            vec![
+                Candidate {
+                    text: "deploy service".to_string(),
+                    kind: CandidateKind::History
+                },
+                Candidate {
+                    text: "deploy.md".to_string(),
+                    kind: CandidateKind::FileName
+                },
+                Candidate {
+                    text: "deployed".to_string(),
+                    kind: CandidateKind::Term
+                },
+                Candidate {
+                    text: "deployment".to_string(),
+                    kind: CandidateKind::Term
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_completes_the_last_word_of_the_query() {
+        let candidates = completer().complete("rollback depl", 1);
+
+        assert_eq!(
+            candidates,
+            vec![Candidate {
+                text: "rollback deploy.md".to_string(),
+                kind: CandidateKind::FileName
+            }]
+        );
+    }
+
+    #[test]
+    fn it_lists_the_most_recent_queries_when_empty() {
+        let candidates = completer().complete("", 10);
+
+        let texts = candidates
+            .iter()
+            .map(|candidate| candidate.text.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(texts, vec!["retries", "deploy service"]);
+    }
+}
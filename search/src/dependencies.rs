@@ -0,0 +1,483 @@
+use std::{fmt, path::Path, path::PathBuf};
+
+use anyhow::Result;
+use config::Config;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    diagnostics::{self, Diagnostic, DiagnosticKind, Diagnostics},
+    walker::Walker,
+};
+
+const MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "Cargo.lock",
+    "package.json",
+    "requirements.txt",
+    "go.mod",
+];
+/// Directories where installed or built dependencies live, their manifests aren't the source's
+const SKIPPED_DIRECTORIES: &[&str] = &["node_modules", "vendor", "target"];
+
+const CARGO_SECTIONS: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+const NPM_SECTIONS: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+    Pip,
+    Go,
+}
+
+impl fmt::Display for Ecosystem {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Ecosystem::Cargo => "cargo",
+            Ecosystem::Npm => "npm",
+            Ecosystem::Pip => "pip",
+            Ecosystem::Go => "go",
+        };
+        formatter.write_str(label)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Dependency {
+    pub source: PathBuf,
+    pub manifest: PathBuf,
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    /// Version requirement of the manifest, or the exact version of lock files
+    pub version: String,
+    /// Where in the manifest it's declared, like `dev-dependencies` or `lock`
+    pub section: String,
+}
+
+/// Dependencies found, along with the manifests that couldn't be read
+#[derive(Debug, Serialize)]
+pub struct DependencyReport {
+    pub dependencies: Vec<Dependency>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Finds which sources depend on a crate, package or module, reading their
+/// `Cargo.toml`, `Cargo.lock`, `package.json`, `requirements.txt` and `go.mod`
+pub struct Dependencies;
+
+impl Dependencies {
+    pub fn find(config: &Config, name: &str) -> DependencyReport {
+        let diagnostics = Diagnostics::default();
+        let mut dependencies = config
+            .config_map
+            .sources
+            .par_iter()
+            .flat_map(|source_path| {
+                let diagnostics = &diagnostics;
+                Walker::source_files_kept(source_path, is_manifest, diagnostics)
+                    .flatten()
+                    .flat_map(move |source_file| {
+                        let declared = match Self::parse(&source_file.path, &source_file.contents) {
+                            Some(Ok(declared)) => declared,
+                            Some(Err(error)) => {
+                                diagnostics::report(
+                                    diagnostics,
+                                    DiagnosticKind::ParseError,
+                                    &source_file.path,
+                                    error,
+                                );
+                                Vec::new()
+                            }
+                            None => Vec::new(),
+                        };
+                        declared
+                            .into_iter()
+                            .filter(|declared| is_named(declared.ecosystem, &declared.name, name))
+                            .map(|declared| Dependency {
+                                source: source_path.clone(),
+                                manifest: source_file.path.clone(),
+                                ecosystem: declared.ecosystem,
+                                name: declared.name,
+                                version: declared.version,
+                                section: declared.section,
+                            })
+                            .collect::<Vec<Dependency>>()
+                    })
+            })
+            .collect::<Vec<Dependency>>();
+        dependencies.sort_by(|a, b| {
+            a.source
+                .cmp(&b.source)
+                .then(a.manifest.cmp(&b.manifest))
+                .then(a.section.cmp(&b.section))
+        });
+
+        DependencyReport {
+            dependencies,
+            diagnostics: diagnostics.into_sorted(),
+        }
+    }
+
+    /// Dependencies declared by the file, or `None` if it isn't a manifest
+    fn parse(path: &Path, contents: &str) -> Option<Result<Vec<Declared>>> {
+        let declared = match path.file_name()?.to_str()? {
+            "Cargo.toml" => cargo_manifest(contents),
+            "Cargo.lock" => cargo_lock(contents),
+            "package.json" => package_json(contents),
+            "requirements.txt" => Ok(requirements(contents)),
+            "go.mod" => Ok(go_mod(contents)),
+            _ => return None,
+        };
+
+        Some(declared)
+    }
+}
+
+/// Walks into every directory but the ones holding installed dependencies, and reads manifests only
+fn is_manifest(name: &str, is_dir: bool) -> bool {
+    match is_dir {
+        true => !SKIPPED_DIRECTORIES.contains(&name),
+        false => MANIFESTS.contains(&name),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Declared {
+    ecosystem: Ecosystem,
+    name: String,
+    version: String,
+    section: String,
+}
+
+impl Declared {
+    fn new(ecosystem: Ecosystem, name: &str, version: &str, section: &str) -> Self {
+        Self {
+            ecosystem,
+            name: name.to_string(),
+            version: match version.trim() {
+                "" => String::from("*"),
+                version => version.to_string(),
+            },
+            section: section.to_string(),
+        }
+    }
+}
+
+/// Python package names are compared the way pip does, Go modules by their
+/// full path or their last segment
+fn is_named(ecosystem: Ecosystem, declared: &str, name: &str) -> bool {
+    match ecosystem {
+        Ecosystem::Pip => normalize_python_name(declared) == normalize_python_name(name),
+        Ecosystem::Go => declared == name || declared.ends_with(&format!("/{}", name)),
+        Ecosystem::Cargo | Ecosystem::Npm => declared == name,
+    }
+}
+
+fn normalize_python_name(name: &str) -> String {
+    name.to_lowercase().replace(&['_', '.'][..], "-")
+}
+
+fn cargo_manifest(contents: &str) -> Result<Vec<Declared>> {
+    let manifest = toml::from_str::<toml::Value>(contents)?;
+    let mut tables = Vec::new();
+    for section in CARGO_SECTIONS {
+        tables.push((section.to_string(), manifest.get(section)));
+    }
+    if let Some(workspace) = manifest.get("workspace") {
+        tables.push((
+            String::from("workspace.dependencies"),
+            workspace.get("dependencies"),
+        ));
+    }
+    if let Some(toml::Value::Table(targets)) = manifest.get("target") {
+        for (target, table) in targets {
+            for section in CARGO_SECTIONS {
+                tables.push((format!("target.{}.{}", target, section), table.get(section)));
+            }
+        }
+    }
+
+    let mut declared = Vec::new();
+    for (section, table) in tables {
+        let table = match table {
+            Some(toml::Value::Table(table)) => table,
+            _ => continue,
+        };
+        for (key, requirement) in table {
+            let (name, version) = match requirement {
+                toml::Value::String(version) => (key.as_str(), version.clone()),
+                toml::Value::Table(details) => {
+                    let name = details
+                        .get("package")
+                        .and_then(|package| package.as_str())
+                        .unwrap_or(key);
+                    let version = match (
+                        details.get("version").and_then(|version| version.as_str()),
+                        details.get("path").and_then(|path| path.as_str()),
+                        details.get("git").and_then(|git| git.as_str()),
+                    ) {
+                        (Some(version), _, _) => version.to_string(),
+                        (None, Some(path), _) => format!("path {}", path),
+                        (None, None, Some(git)) => format!("git {}", git),
+                        _ if details.contains_key("workspace") => String::from("workspace"),
+                        _ => String::new(),
+                    };
+                    (name, version)
+                }
+                _ => continue,
+            };
+            declared.push(Declared::new(Ecosystem::Cargo, name, &version, &section));
+        }
+    }
+
+    Ok(declared)
+}
+
+fn cargo_lock(contents: &str) -> Result<Vec<Declared>> {
+    let lock = toml::from_str::<toml::Value>(contents)?;
+    let packages = match lock.get("package") {
+        Some(toml::Value::Array(packages)) => packages,
+        _ => return Ok(Vec::new()),
+    };
+
+    Ok(packages
+        .iter()
+        .filter_map(|package| {
+            let name = package.get("name")?.as_str()?;
+            let version = package.get("version")?.as_str()?;
+            Some(Declared::new(Ecosystem::Cargo, name, version, "lock"))
+        })
+        .collect())
+}
+
+fn package_json(contents: &str) -> Result<Vec<Declared>> {
+    let package = serde_json::from_str::<serde_json::Value>(contents)?;
+
+    Ok(NPM_SECTIONS
+        .iter()
+        .filter_map(|section| Some((section, package.get(section)?.as_object()?)))
+        .flat_map(|(section, dependencies)| {
+            dependencies.iter().map(move |(name, version)| {
+                Declared::new(
+                    Ecosystem::Npm,
+                    name,
+                    version.as_str().unwrap_or_default(),
+                    section,
+                )
+            })
+        })
+        .collect())
+}
+
+/// Lines like `requests[socks]>=2.25,<3 ; python_version > "3.6"`, options
+/// such as `-r other.txt` and URLs are skipped
+fn requirements(contents: &str) -> Vec<Declared> {
+    contents
+        .lines()
+        .map(|line| line.split(" #").next().unwrap_or_default().trim())
+        .filter(|line| {
+            !line.is_empty() && !line.starts_with(&['#', '-'][..]) && !line.contains("://")
+        })
+        .filter_map(|line| {
+            let requirement = line.split(';').next()?.trim();
+            let name_end = requirement
+                .find(|character: char| !(character.is_alphanumeric() || "-_.".contains(character)))
+                .unwrap_or(requirement.len());
+            let (name, version) = requirement.split_at(name_end);
+            let version = match version.trim_start().strip_prefix('[') {
+                Some(extras) => extras.split_once(']')?.1,
+                None => version,
+            };
+            match name {
+                "" => None,
+                name => Some(Declared::new(Ecosystem::Pip, name, version, "requirements")),
+            }
+        })
+        .collect()
+}
+
+/// `require` lines and blocks, indirect dependencies included
+fn go_mod(contents: &str) -> Vec<Declared> {
+    let mut declared = Vec::new();
+    let mut in_block = false;
+    for line in contents.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        let requirement = if in_block {
+            if line == ")" {
+                in_block = false;
+                continue;
+            }
+            line
+        } else if let Some(rest) = line.strip_prefix("require") {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_block = true;
+                continue;
+            }
+            rest
+        } else {
+            continue;
+        };
+
+        let mut parts = requirement.split_whitespace();
+        if let (Some(module), Some(version)) = (parts.next(), parts.next()) {
+            declared.push(Declared::new(Ecosystem::Go, module, version, "require"));
+        }
+    }
+
+    declared
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use config::Configuration;
+    use tempfile::tempdir;
+
+    fn parsed(path: &str, contents: &str) -> Vec<(String, String, String)> {
+        Dependencies::parse(Path::new(path), contents)
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|declared| (declared.name, declared.version, declared.section))
+            .collect()
+    }
+
+    fn dependency(name: &str, version: &str, section: &str) -> (String, String, String) {
+        (name.to_string(), version.to_string(), section.to_string())
+    }
+
+    #[test]
+    fn it_reads_cargo_manifests_and_locks() {
+        let manifest = r#"
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+yaml = { package = "serde_yaml", version = "0.8" }
+config = { path = "../config" }
+
+[target.'cfg(unix)'.dev-dependencies]
+tempfile = "3"
+"#;
+        assert_eq!(
+            parsed("Cargo.toml", manifest),
+            vec![
+                dependency("config", "path ../config", "dependencies"),
+                dependency("serde", "1.0", "dependencies"),
+                dependency("serde_yaml", "0.8", "dependencies"),
+                dependency("tempfile", "3", "target.cfg(unix).dev-dependencies"),
+            ]
+        );
+
+        let lock = "[[package]]\nname = \"serde\"\nversion = \"1.0.130\"\n";
+        assert_eq!(
+            parsed("Cargo.lock", lock),
+            vec![dependency("serde", "1.0.130", "lock")]
+        );
+        assert!(Dependencies::parse(Path::new("Cargo.toml"), "[")
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn it_reads_npm_pip_and_go_manifests() {
+        assert_eq!(
+            parsed(
+                "package.json",
+                r#"{"dependencies": {"react": "^17.0.2"}, "devDependencies": {"jest": "27"}}"#
+            ),
+            vec![
+                dependency("react", "^17.0.2", "dependencies"),
+                dependency("jest", "27", "devDependencies"),
+            ]
+        );
+        assert_eq!(
+            parsed(
+                "requirements.txt",
+                "# pinned\nrequests[socks]>=2.25,<3 ; python_version > \"3.6\"\n-r dev.txt\nDjango==3.2  # LTS\nflask\n"
+            ),
+            vec![
+                dependency("requests", ">=2.25,<3", "requirements"),
+                dependency("Django", "==3.2", "requirements"),
+                dependency("flask", "*", "requirements"),
+            ]
+        );
+        assert_eq!(
+            parsed(
+                "go.mod",
+                "module example.com/app\n\nrequire github.com/pkg/errors v0.9.1\n\nrequire (\n\tgithub.com/spf13/cobra v1.2.1\n\tgolang.org/x/sys v0.0.1 // indirect\n)\n"
+            ),
+            vec![
+                dependency("github.com/pkg/errors", "v0.9.1", "require"),
+                dependency("github.com/spf13/cobra", "v1.2.1", "require"),
+                dependency("golang.org/x/sys", "v0.0.1", "require"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_finds_the_sources_using_a_dependency() -> Result<()> {
+        let first_source = tempdir()?;
+        let second_source = tempdir()?;
+        fs::write(
+            first_source.path().join("Cargo.toml"),
+            "[dependencies]\nserde = \"1.0\"\n",
+        )?;
+        fs::write(
+            second_source.path().join("requirements.txt"),
+            "Serde_Lite==0.1\n",
+        )?;
+        fs::write(
+            second_source.path().join("go.mod"),
+            "require github.com/x/serde v1.0.0\n",
+        )?;
+        fs::write(second_source.path().join("package.json"), "{ broken")?;
+        let installed = first_source.path().join("node_modules").join("serde");
+        fs::create_dir_all(&installed)?;
+        fs::write(
+            installed.join("package.json"),
+            r#"{ "dependencies": { "serde": "1" } }"#,
+        )?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(first_source.path())?;
+        config.add_source(second_source.path())?;
+
+        let report = Dependencies::find(&config, "serde");
+
+        let found = report
+            .dependencies
+            .iter()
+            .map(|dependency| (dependency.ecosystem, dependency.version.as_str()))
+            .collect::<Vec<(Ecosystem, &str)>>();
+        let mut expected = vec![(Ecosystem::Cargo, "1.0"), (Ecosystem::Go, "v1.0.0")];
+        if second_source.path() < first_source.path() {
+            expected.reverse();
+        }
+        assert_eq!(found, expected);
+        assert_eq!(
+            report
+                .diagnostics
+                .iter()
+                .map(|diagnostic| (diagnostic.kind, diagnostic.path.clone()))
+                .collect::<Vec<(DiagnosticKind, PathBuf)>>(),
+            vec![(
+                DiagnosticKind::ParseError,
+                second_source.path().join("package.json")
+            )]
+        );
+        assert_eq!(
+            Dependencies::find(&config, "serde-lite").dependencies.len(),
+            1
+        );
+        Ok(())
+    }
+}
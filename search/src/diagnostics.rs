@@ -0,0 +1,156 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticKind {
+    UnreadableFile,
+    PermissionDenied,
+    NotUtf8,
+    WalkError,
+    UnreadableSource,
+    ParseError,
+    CacheError,
+}
+
+impl DiagnosticKind {
+    /// Permission errors are told apart from any other failure to read a file
+    pub(crate) fn of_io_error(error: &io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => DiagnosticKind::PermissionDenied,
+            _ => DiagnosticKind::UnreadableFile,
+        }
+    }
+
+    /// Name of the kind for a number of diagnostics, like "2 parse errors"
+    pub fn describe(self, count: usize) -> String {
+        let (one, many) = match self {
+            DiagnosticKind::UnreadableFile => ("unreadable file", "unreadable files"),
+            DiagnosticKind::PermissionDenied => ("permission error", "permission errors"),
+            DiagnosticKind::NotUtf8 => ("file that isn't UTF-8", "files that aren't UTF-8"),
+            DiagnosticKind::WalkError => ("walk error", "walk errors"),
+            DiagnosticKind::UnreadableSource => ("unreadable source", "unreadable sources"),
+            DiagnosticKind::ParseError => ("parse error", "parse errors"),
+            DiagnosticKind::CacheError => ("cache error", "cache errors"),
+        };
+        match count {
+            1 => format!("1 {}", one),
+            count => format!("{} {}", count, many),
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            DiagnosticKind::UnreadableFile => "unreadable file",
+            DiagnosticKind::PermissionDenied => "permission denied",
+            DiagnosticKind::NotUtf8 => "not UTF-8",
+            DiagnosticKind::WalkError => "walk error",
+            DiagnosticKind::UnreadableSource => "unreadable source",
+            DiagnosticKind::ParseError => "parse error",
+            DiagnosticKind::CacheError => "cache error",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// Something that went wrong with a file or a source while searching, the
+/// search goes on without it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:?}, {}", self.kind, self.path, self.message)
+    }
+}
+
+/// Collects the diagnostics of a search across the thread pool
+#[derive(Debug, Default)]
+pub(crate) struct Diagnostics(Mutex<Vec<Diagnostic>>);
+
+impl Diagnostics {
+    pub(crate) fn into_sorted(self) -> Vec<Diagnostic> {
+        let mut diagnostics = self
+            .0
+            .into_inner()
+            .unwrap_or_else(|error| error.into_inner());
+        diagnostics.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.path.cmp(&b.path)));
+        diagnostics
+    }
+
+    fn push(&self, diagnostic: Diagnostic) {
+        if let Ok(mut diagnostics) = self.0.lock() {
+            diagnostics.push(diagnostic);
+        }
+    }
+}
+
+/// Adds to the diagnostics of the search or command, which are then shown
+/// together instead of printed as they come
+pub(crate) fn report<M: fmt::Display>(
+    diagnostics: &Diagnostics,
+    kind: DiagnosticKind,
+    path: &Path,
+    message: M,
+) {
+    diagnostics.push(Diagnostic {
+        kind,
+        path: path.to_path_buf(),
+        message: message.to_string(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_sorts_the_diagnostics_by_kind_and_path() {
+        let diagnostics = Diagnostics::default();
+
+        report(
+            &diagnostics,
+            DiagnosticKind::ParseError,
+            Path::new("b.json"),
+            "expected value",
+        );
+        report(
+            &diagnostics,
+            DiagnosticKind::of_io_error(&io::Error::from(io::ErrorKind::PermissionDenied)),
+            Path::new("c.txt"),
+            "permission denied",
+        );
+        report(
+            &diagnostics,
+            DiagnosticKind::ParseError,
+            Path::new("a.yaml"),
+            "invalid indentation",
+        );
+
+        let found = diagnostics
+            .into_sorted()
+            .into_iter()
+            .map(|diagnostic| (diagnostic.kind, diagnostic.path))
+            .collect::<Vec<(DiagnosticKind, PathBuf)>>();
+        assert_eq!(
+            found,
+            vec![
+                (DiagnosticKind::PermissionDenied, PathBuf::from("c.txt")),
+                (DiagnosticKind::ParseError, PathBuf::from("a.yaml")),
+                (DiagnosticKind::ParseError, PathBuf::from("b.json")),
+            ]
+        );
+        assert_eq!(DiagnosticKind::ParseError.describe(2), "2 parse errors");
+    }
+}
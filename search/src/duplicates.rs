@@ -0,0 +1,372 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    path::PathBuf,
+};
+
+use config::Config;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    diagnostics::{Diagnostic, Diagnostics},
+    tokenizer::tokenize,
+    walker::Walker,
+};
+
+/// Words per shingle, files with fewer words are only checked for exact duplicates
+const SHINGLE_SIZE: usize = 5;
+const BANDS: usize = 16;
+const ROWS_PER_BAND: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateKind {
+    Exact,
+    Near,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateFile {
+    pub source: PathBuf,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    /// Lowest estimated similarity between the files of the group, 1 for exact duplicates
+    pub similarity: f64,
+    pub files: Vec<DuplicateFile>,
+}
+
+/// Groups of duplicates found, along with the files that couldn't be read
+#[derive(Debug, Serialize)]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+struct Fingerprint {
+    file: DuplicateFile,
+    digest: blake3::Hash,
+    signature: Option<Vec<u64>>,
+}
+
+/// Finds files with the same contents across all sources, and files whose
+/// contents mostly overlap. Overlap is estimated with MinHash signatures of
+/// word shingles, only comparing files that share a band of their signature.
+pub struct Duplicates;
+
+impl Duplicates {
+    pub fn find(config: &Config, threshold: f64) -> DuplicateReport {
+        let diagnostics = Diagnostics::default();
+        let fingerprints = config
+            .config_map
+            .sources
+            .par_iter()
+            .flat_map(|source_path| {
+                Walker::source_bytes(source_path, None, &diagnostics)
+                    .flatten()
+                    .filter(|source_file| !source_file.contents.is_empty())
+                    .map(move |source_file| Fingerprint {
+                        digest: blake3::hash(&source_file.contents),
+                        // Files that aren't text only have exact duplicates
+                        signature: std::str::from_utf8(&source_file.contents)
+                            .ok()
+                            .and_then(Self::signature),
+                        file: DuplicateFile {
+                            source: source_path.clone(),
+                            path: source_file.path,
+                            size: source_file.contents.len() as u64,
+                        },
+                    })
+            })
+            .collect::<Vec<Fingerprint>>();
+
+        let mut by_digest = HashMap::<blake3::Hash, Vec<&Fingerprint>>::new();
+        for fingerprint in &fingerprints {
+            by_digest
+                .entry(fingerprint.digest)
+                .or_default()
+                .push(fingerprint);
+        }
+        let unique_contents = by_digest
+            .values()
+            .map(|same_contents| same_contents[0])
+            .collect::<Vec<&Fingerprint>>();
+
+        let mut groups = by_digest
+            .values()
+            .filter(|same_contents| same_contents.len() > 1)
+            .map(|same_contents| DuplicateGroup {
+                kind: DuplicateKind::Exact,
+                similarity: 1.0,
+                files: same_contents
+                    .iter()
+                    .map(|fingerprint| fingerprint.file.clone())
+                    .collect(),
+            })
+            .chain(Self::near_duplicates(&unique_contents, threshold))
+            .map(|mut group| {
+                group.files.sort_by(|a, b| a.path.cmp(&b.path));
+                group
+            })
+            .collect::<Vec<DuplicateGroup>>();
+        groups.sort_by(|a, b| {
+            let size =
+                |group: &DuplicateGroup| group.files.iter().map(|file| file.size).sum::<u64>();
+            size(b)
+                .cmp(&size(a))
+                .then(a.files[0].path.cmp(&b.files[0].path))
+        });
+
+        DuplicateReport {
+            groups,
+            diagnostics: diagnostics.into_sorted(),
+        }
+    }
+
+    /// Groups files linked by an estimated similarity of at least the threshold.
+    /// Files of a group may only be linked through others, so its similarity is
+    /// the lowest of every pair of its files.
+    fn near_duplicates(fingerprints: &[&Fingerprint], threshold: f64) -> Vec<DuplicateGroup> {
+        let mut buckets = HashMap::<(usize, &[u64]), Vec<usize>>::new();
+        for (index, fingerprint) in fingerprints.iter().enumerate() {
+            if let Some(signature) = &fingerprint.signature {
+                for (band, rows) in signature.chunks(ROWS_PER_BAND).enumerate() {
+                    buckets.entry((band, rows)).or_default().push(index);
+                }
+            }
+        }
+
+        let mut candidates = HashSet::<(usize, usize)>::new();
+        for indexes in buckets.values() {
+            for (position, first) in indexes.iter().enumerate() {
+                for second in &indexes[position + 1..] {
+                    candidates.insert((*first, *second));
+                }
+            }
+        }
+
+        let mut parents = (0..fingerprints.len()).collect::<Vec<usize>>();
+        for (first, second) in candidates {
+            let similarity = match (
+                &fingerprints[first].signature,
+                &fingerprints[second].signature,
+            ) {
+                (Some(first), Some(second)) => Self::similarity(first, second),
+                _ => continue,
+            };
+            if similarity < threshold {
+                continue;
+            }
+            let (first_root, second_root) = (
+                Self::root(&mut parents, first),
+                Self::root(&mut parents, second),
+            );
+            parents[second_root] = first_root;
+        }
+
+        let mut members = HashMap::<usize, Vec<&Fingerprint>>::new();
+        for (index, fingerprint) in fingerprints.iter().enumerate() {
+            let root = Self::root(&mut parents, index);
+            members.entry(root).or_default().push(fingerprint);
+        }
+
+        members
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .map(|group| DuplicateGroup {
+                kind: DuplicateKind::Near,
+                similarity: Self::lowest_similarity(&group),
+                files: group
+                    .iter()
+                    .map(|fingerprint| fingerprint.file.clone())
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn lowest_similarity(group: &[&Fingerprint]) -> f64 {
+        let mut lowest = 1.0_f64;
+        for (position, first) in group.iter().enumerate() {
+            for second in &group[position + 1..] {
+                if let (Some(first), Some(second)) = (&first.signature, &second.signature) {
+                    lowest = lowest.min(Self::similarity(first, second));
+                }
+            }
+        }
+
+        lowest
+    }
+
+    fn root(parents: &mut [usize], index: usize) -> usize {
+        let mut root = index;
+        while parents[root] != root {
+            root = parents[root];
+        }
+        parents[index] = root;
+        root
+    }
+
+    /// MinHash of the word shingles of the contents
+    fn signature(contents: &str) -> Option<Vec<u64>> {
+        let words = tokenize(contents)
+            .into_iter()
+            .map(|token| token.term)
+            .collect::<Vec<String>>();
+        if words.len() < SHINGLE_SIZE {
+            return None;
+        }
+
+        let shingles = words
+            .windows(SHINGLE_SIZE)
+            .map(|shingle| {
+                let mut hasher = DefaultHasher::new();
+                shingle.hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect::<HashSet<u64>>();
+
+        Some(
+            (0..BANDS * ROWS_PER_BAND)
+                .map(|seed| {
+                    shingles
+                        .iter()
+                        .map(|shingle| Self::mix(shingle ^ Self::mix(seed as u64)))
+                        .min()
+                        .unwrap_or_default()
+                })
+                .collect(),
+        )
+    }
+
+    /// Share of equal minimums, an estimate of the Jaccard similarity of the shingles
+    fn similarity(first: &[u64], second: &[u64]) -> f64 {
+        let equal = first.iter().zip(second).filter(|(a, b)| a == b).count();
+        equal as f64 / first.len() as f64
+    }
+
+    /// SplitMix64 finalizer, turns one hash into an unrelated looking one per seed
+    fn mix(value: u64) -> u64 {
+        let mut value = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        value ^ (value >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use anyhow::Result;
+    use config::Configuration;
+    use tempfile::tempdir;
+
+    const VENDORED: &str = "Licensed under the Apache License, Version 2.0 (the \"License\"); you may not use this file except in compliance with the License. You may obtain a copy of the License at http://www.apache.org/licenses/LICENSE-2.0";
+
+    #[test]
+    fn it_finds_exact_and_near_duplicates_across_sources() -> Result<()> {
+        let first_source = tempdir()?;
+        let second_source = tempdir()?;
+        fs::write(first_source.path().join("LICENSE"), VENDORED)?;
+        fs::write(second_source.path().join("LICENSE"), VENDORED)?;
+        fs::write(
+            second_source.path().join("LICENSE.old"),
+            VENDORED.replace("2.0", "1.1"),
+        )?;
+        fs::write(first_source.path().join("main.rs"), "fn main() {}")?;
+        fs::write(second_source.path().join("empty.txt"), "")?;
+        fs::write(first_source.path().join("other_empty.txt"), "")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(first_source.path())?;
+        config.add_source(second_source.path())?;
+
+        let groups = Duplicates::find(&config, 0.5).groups;
+
+        assert_eq!(groups.len(), 2);
+        let exact = groups
+            .iter()
+            .find(|group| group.kind == DuplicateKind::Exact)
+            .unwrap();
+        assert_eq!(exact.files.len(), 2);
+        assert_eq!(exact.files[0].size, VENDORED.len() as u64);
+        let near = groups
+            .iter()
+            .find(|group| group.kind == DuplicateKind::Near)
+            .unwrap();
+        assert_eq!(near.files.len(), 2);
+        assert!(near.similarity >= 0.5 && near.similarity < 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn it_finds_exact_duplicates_of_files_that_are_not_text() -> Result<()> {
+        let source = tempdir()?;
+        let image = [0x89, 0x50, 0x4e, 0x47, 0xff, 0xfe, 0x00, 0x01];
+        fs::write(source.path().join("logo.png"), image)?;
+        fs::write(source.path().join("logo_copy.png"), image)?;
+        fs::write(source.path().join("icon.png"), [0xff, 0xfe, 0x00, 0x02])?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+
+        let groups = Duplicates::find(&config, 0.5).groups;
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].kind, DuplicateKind::Exact);
+        assert_eq!(
+            groups[0]
+                .files
+                .iter()
+                .map(|file| file.path.file_name().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["logo.png", "logo_copy.png"]
+        );
+        assert_eq!(groups[0].files[0].size, image.len() as u64);
+        Ok(())
+    }
+
+    #[test]
+    fn it_reports_the_lowest_similarity_of_every_pair_of_a_group() {
+        let half = BANDS * ROWS_PER_BAND / 2;
+        let fingerprint = |path: &str, first_half: u64, second_half: u64| Fingerprint {
+            file: DuplicateFile {
+                source: PathBuf::from("source"),
+                path: PathBuf::from(path),
+                size: 1,
+            },
+            digest: blake3::hash(path.as_bytes()),
+            signature: Some([vec![first_half; half], vec![second_half; half]].concat()),
+        };
+        // a is like b and b is like c, while a and c have nothing in common
+        let (a, b, c) = (
+            fingerprint("a", 0, 0),
+            fingerprint("b", 0, 1),
+            fingerprint("c", 2, 1),
+        );
+
+        let groups = Duplicates::near_duplicates(&[&a, &b, &c], 0.5);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].files.len(), 3);
+        assert_eq!(groups[0].similarity, 0.0);
+    }
+
+    #[test]
+    fn it_estimates_the_similarity_of_the_shingles() {
+        let signature = Duplicates::signature(VENDORED).unwrap();
+        let other_signature =
+            Duplicates::signature("Completely unrelated notes about the deploy of the service")
+                .unwrap();
+
+        assert_eq!(Duplicates::similarity(&signature, &signature), 1.0);
+        assert!(Duplicates::similarity(&signature, &other_signature) < 0.1);
+        assert_eq!(Duplicates::signature("too short"), None);
+    }
+}
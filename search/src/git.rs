@@ -0,0 +1,212 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdout, Command, Output, Stdio},
+    thread, vec,
+};
+
+use anyhow::{anyhow, Result};
+
+pub fn is_repository<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref().join(".git").exists()
+}
+
+/// A commit of a git source, read straight from the object database so the
+/// working tree is never checked out or modified.
+#[derive(Debug)]
+pub struct Revision<'repo> {
+    repository: &'repo Path,
+    commit: String,
+}
+
+impl<'repo> Revision<'repo> {
+    /// Finds the commit of a ref of the repository, or of the same ref of its
+    /// `origin` remote once it's been fetched. Nothing is fetched here.
+    pub fn resolve(repository: &'repo Path, git_ref: &str) -> Result<Self> {
+        check_ref(git_ref)?;
+        let commit = Self::rev_parse(repository, git_ref).or_else(|error| {
+            Self::rev_parse(repository, &format!("refs/remotes/origin/{}", git_ref))
+                .map_err(|_| error)
+        })?;
+
+        Ok(Self { repository, commit })
+    }
+
+    pub fn commit(&self) -> &str {
+        &self.commit
+    }
+
+    pub fn files(&self) -> Result<Vec<PathBuf>> {
+        let output = git(
+            self.repository,
+            &["ls-tree", "-r", "-z", "--name-only", &self.commit],
+        )?;
+
+        let files = output
+            .stdout
+            .split(|byte| *byte == b'\0')
+            .filter(|path| !path.is_empty())
+            .map(|path| PathBuf::from(String::from_utf8_lossy(path).into_owned()))
+            .collect();
+
+        Ok(files)
+    }
+
+    /// Contents of the files, in the order of their paths, all of them read by
+    /// a single `git cat-file` process
+    pub fn read(&self, paths: Vec<PathBuf>) -> Result<Blobs> {
+        let mut child = Command::new("git")
+            .arg("-C")
+            .arg(self.repository)
+            .args(["cat-file", "--batch"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let (mut stdin, stdout) = match (child.stdin.take(), child.stdout.take()) {
+            (Some(stdin), Some(stdout)) => (stdin, stdout),
+            _ => return Err(anyhow!("git cat-file has no stdin or stdout")),
+        };
+        let objects = paths
+            .iter()
+            .map(|path| path.to_string_lossy())
+            .filter(|path| !path.contains('\n'))
+            .map(|path| format!("{}:{}\n", self.commit, path))
+            .collect::<String>();
+        // Blobs are read while the objects are still being written, otherwise both
+        // pipes could fill up. Writing fails once the blobs are dropped early.
+        thread::spawn(move || stdin.write_all(objects.as_bytes()));
+
+        Ok(Blobs {
+            child,
+            stdout: BufReader::new(stdout),
+            paths: paths.into_iter(),
+        })
+    }
+
+    fn rev_parse(repository: &Path, git_ref: &str) -> Result<String> {
+        let output = git(
+            repository,
+            &["rev-parse", "--verify", &format!("{}^{{commit}}", git_ref)],
+        )?;
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+/// Files read from `git cat-file --batch`, along with their paths. The process
+/// is killed when they're dropped.
+pub struct Blobs {
+    child: Child,
+    stdout: BufReader<ChildStdout>,
+    paths: vec::IntoIter<PathBuf>,
+}
+
+impl Blobs {
+    fn read_blob(&mut self, path: &Path) -> Result<Vec<u8>> {
+        if path.to_string_lossy().contains('\n') {
+            return Err(anyhow!("git cat-file can't read paths with line breaks"));
+        }
+        let mut header = String::new();
+        if self.stdout.read_line(&mut header)? == 0 {
+            return Err(anyhow!("git cat-file stopped early"));
+        }
+        // Either `<object> <type> <size>`, followed by the contents, or `<object> missing`
+        let header = header.trim_end();
+        let fields = header.rsplitn(3, ' ').collect::<Vec<&str>>();
+        let (kind, size) = match fields.as_slice() {
+            [size, kind, _] => match size.parse::<usize>() {
+                Ok(size) => (*kind, size),
+                Err(_) => return Err(anyhow!("git cat-file failed: {}", header)),
+            },
+            _ => return Err(anyhow!("git cat-file failed: {}", header)),
+        };
+        let mut contents = vec![0; size + 1];
+        self.stdout.read_exact(&mut contents)?;
+        contents.pop();
+
+        match kind {
+            "blob" => Ok(contents),
+            kind => Err(anyhow!("{:?} is a {}, not a file", path, kind)),
+        }
+    }
+}
+
+impl Iterator for Blobs {
+    type Item = (PathBuf, Result<Vec<u8>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let path = self.paths.next()?;
+        let contents = self.read_blob(&path);
+        Some((path, contents))
+    }
+}
+
+impl Drop for Blobs {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Fetches a branch, tag or commit from the `origin` remote, for sources that
+/// are shallow clones without it. It's kept as `origin/<ref>`, where
+/// `Revision::resolve` finds it.
+pub fn fetch(repository: &Path, git_ref: &str) -> Result<()> {
+    check_ref(git_ref)?;
+    let refspec = format!("+{}:refs/remotes/origin/{}", git_ref, git_ref);
+    git(repository, &["fetch", "--depth", "1", "origin", &refspec])?;
+
+    Ok(())
+}
+
+/// Refs are passed to git as arguments, so they can't look like an option
+fn check_ref(git_ref: &str) -> Result<()> {
+    match git_ref.starts_with('-') || git_ref.is_empty() {
+        true => Err(anyhow!("Invalid git ref: {:?}", git_ref)),
+        false => Ok(()),
+    }
+}
+
+/// Commit checked out in the working tree, `None` before the first commit
+pub fn head(repository: &Path) -> Option<String> {
+    Revision::rev_parse(repository, "HEAD").ok()
+}
+
+/// Author of each line of a file in the working tree, `None` for the lines
+/// that aren't committed yet
+pub fn blame(repository: &Path, path: &Path) -> Result<Vec<Option<String>>> {
+    let output = git(
+        repository,
+        &["blame", "--line-porcelain", "--", &path.to_string_lossy()],
+    )?;
+
+    let authors = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix("author "))
+        .map(|author| match author {
+            "Not Committed Yet" => None,
+            author => Some(author.to_string()),
+        })
+        .collect();
+
+    Ok(authors)
+}
+
+fn git(repository: &Path, args: &[&str]) -> Result<Output> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repository)
+        .args(args)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(output)
+}
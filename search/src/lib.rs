@@ -1,63 +1,422 @@
 use core::panic;
-use std::{fs::File, io::Read};
+use std::{path::PathBuf, time::Instant};
 
-use anyhow::Result;
-use config::Config;
-use jwalk::{Parallelism, WalkDir};
+use anyhow::{anyhow, Result};
+use config::{Config, Configuration};
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+mod analyzer;
+mod bytes;
+mod cache;
+mod completion;
+mod dependencies;
+mod diagnostics;
+mod duplicates;
+mod git;
+mod licenses;
+mod limits;
+mod logs;
+mod matcher;
+mod normalization;
+mod proximity;
+mod secrets;
+mod similarity;
+mod stats;
+mod structured;
+mod symbols;
+mod syntax;
+mod todos;
+pub mod tokenizer;
+mod vocabulary;
+mod walker;
+
+pub use crate::analyzer::Analyzer;
+use crate::bytes::ByteMatcher;
+pub use crate::bytes::{parse_escaped, parse_hex};
+pub use crate::cache::ResultCache;
+pub use crate::completion::{Candidate, CandidateKind, Completer};
+pub use crate::dependencies::{Dependencies, Dependency, DependencyReport, Ecosystem};
+use crate::diagnostics::Diagnostics;
+pub use crate::diagnostics::{Diagnostic, DiagnosticKind};
+pub use crate::duplicates::{
+    DuplicateFile, DuplicateGroup, DuplicateKind, DuplicateReport, Duplicates,
+};
+pub use crate::licenses::{LicenseFile, LicenseReport, Licenses};
+use crate::limits::Budget;
+pub use crate::limits::{parse_size, Limit, Limits};
+pub use crate::logs::TimeRange;
+use crate::matcher::Matcher;
+pub use crate::matcher::{read_patterns, Match, MatchMode};
+pub use crate::secrets::{Allowlist, SecretFinding, SecretReport, Secrets, Severity};
+pub use crate::similarity::{SimilarFile, SimilarFiles, Similarity};
+use crate::stats::Counters;
+pub use crate::stats::{GroupStats, SearchStats};
+pub use crate::structured::{PathExpr, StructuredQuery};
+pub use crate::symbols::{Symbol, SymbolKind, SymbolReport, Symbols};
+use crate::syntax::Syntax;
+pub use crate::syntax::SyntaxScope;
+pub use crate::todos::{Todo, TodoGroup, TodoReport, Todos};
+pub use crate::vocabulary::{Suggestion, Vocabulary};
+use crate::walker::{SourceFile, Walker};
+
+#[derive(Debug, Default, Clone)]
+pub struct SearchOptions {
+    /// Any of these is a match, they're all searched for in one pass over each file
+    pub patterns: Vec<String>,
+    /// Branch, tag or commit to search in git sources instead of their working tree
+    pub git_ref: Option<String>,
+    pub mode: MatchMode,
+    /// Treat the patterns as regular expressions that can span line breaks
+    pub multiline: bool,
+    /// Match regardless of accents and of how the text is unicode normalized
+    pub ignore_diacritics: bool,
+    /// Byte sequences to find in files of any kind. When there are any, the text
+    /// patterns are searched for as their UTF-8 bytes, numbered before these.
+    pub byte_patterns: Vec<Vec<u8>>,
+    /// Match the nodes of JSON, YAML and TOML files instead of any patterns
+    pub structured: Option<StructuredQuery>,
+    /// Search log files entry by entry, keeping the ones timestamped within the range
+    pub log: Option<TimeRange>,
+    /// Only count matches in the comments, string literals or code of the
+    /// source files whose language is known, other files are skipped
+    pub scope: Option<SyntaxScope>,
+    pub limits: Limits,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub source: PathBuf,
+    pub path: PathBuf,
+    pub contents: String,
+    /// Raw contents of files searched for byte patterns, `contents` is empty then
+    pub bytes: Option<Vec<u8>>,
+    pub matches: Vec<Match>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub results: Vec<SearchResult>,
+    pub stats: SearchStats,
+    /// Close terms found in the sources, only when nothing matched
+    pub suggestions: Vec<Suggestion>,
+    /// Files and sources that couldn't be searched, and why
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+const MAX_SUGGESTIONS: usize = 9;
+
+/// Sizes the thread pool every search runs on, which also bounds how many files
+/// are read at once. It has to be set before the first search, otherwise there's
+/// one thread per CPU.
+pub fn init_thread_pool(threads: usize) -> Result<()> {
+    if threads == 0 {
+        return Err(anyhow!("Searches need at least one thread"));
+    }
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()?;
+
+    Ok(())
+}
+
 pub struct Search;
 
 impl Search {
     pub fn by_term(config: &Config, term: String) -> Result<Vec<String>> {
+        let options = SearchOptions {
+            patterns: vec![term],
+            ..SearchOptions::default()
+        };
+        let search_results = Self::with_options(config, &options)?;
+
+        Ok(search_results
+            .results
+            .into_iter()
+            .map(|result| result.contents)
+            .collect())
+    }
+
+    /// Fetches a branch, tag or commit from the `origin` remote of every git
+    /// source, so it can be searched. Searches never fetch on their own.
+    pub fn fetch(config: &Config, git_ref: &str) -> Result<()> {
+        for source_path in &config.config_map.sources {
+            if git::is_repository(source_path) {
+                git::fetch(source_path, git_ref).map_err(|error| {
+                    anyhow!(
+                        "Unable to fetch {} into {:?}, due to {}",
+                        git_ref,
+                        source_path,
+                        error
+                    )
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn with_options(config: &Config, options: &SearchOptions) -> Result<SearchResults> {
+        let started_at = Instant::now();
         let sources_list = &config.config_map.sources;
         if !sources_list.is_empty() {
-            let files_with_term = sources_list
-                .par_iter()
-                .flat_map(|source_path| {
-                    println!("Walking source: {:?}", source_path);
-                    WalkDir::new(source_path)
-                        .parallelism(Parallelism::RayonNewPool(5)) // Needs to use a different thread pool to prevent any livelocks, 5 is an arbitrary number
-                        .into_iter()
-                        .par_bridge()
-                })
-                .filter_map(|result| result.ok())
-                .filter(|dir_entry| match dir_entry.metadata() {
-                    Ok(metadata) => !metadata.is_dir(),
-                    Err(_) => false,
-                })
-                .map(|dir_entry| {
-                    let mut file = match File::open(dir_entry.path()) {
-                        Ok(file) => file,
-                        Err(error) => {
-                            eprintln!(
-                                "Unable to read file: {:?}, due to {:?}",
-                                dir_entry.path(),
-                                error
-                            );
-                            return String::new();
+            let has_patterns = !options.patterns.is_empty() || !options.byte_patterns.is_empty();
+            let has_structured_query = matches!(&options.structured, Some(structured_query) if !structured_query.is_empty());
+            let has_time_range =
+                matches!(&options.log, Some(time_range) if !time_range.is_unbounded());
+            if !has_patterns && !has_structured_query && !has_time_range {
+                return Err(anyhow!("No patterns to search for"));
+            }
+            let counters = Counters::default();
+            let budget = Budget::new(options.limits);
+            let diagnostics = Diagnostics::default();
+            let mut results = match (&options.structured, options.byte_patterns.is_empty()) {
+                (Some(structured_query), _) => Self::search_structured(
+                    config,
+                    options,
+                    structured_query,
+                    &counters,
+                    &budget,
+                    &diagnostics,
+                )?,
+                (None, true) => {
+                    Self::search_text(config, options, &counters, &budget, &diagnostics)?
+                }
+                (None, false) => {
+                    Self::search_bytes(config, options, &counters, &budget, &diagnostics)?
+                }
+            };
+            let limits_hit = budget.finish(&mut results);
+            // A search cut short could have matched later on. Only searches without
+            // results read the vocabulary, within the same limits.
+            let is_text_search = options.byte_patterns.is_empty()
+                && options.structured.is_none()
+                && limits_hit.is_empty();
+            let suggestions = match (results.is_empty(), &options.mode) {
+                (true, MatchMode::Matching) if is_text_search => {
+                    Vocabulary::build_within(config, options.git_ref.as_deref(), options.limits)
+                        .suggest_patterns(&options.patterns, MAX_SUGGESTIONS)
+                }
+                _ => Vec::new(),
+            };
+            let stats = SearchStats::new(&results, counters, started_at.elapsed(), limits_hit);
+
+            Ok(SearchResults {
+                results,
+                stats,
+                suggestions,
+                diagnostics: diagnostics.into_sorted(),
+            })
+        } else {
+            panic!("No available sources to search through")
+        }
+    }
+
+    fn search_text(
+        config: &Config,
+        options: &SearchOptions,
+        counters: &Counters,
+        budget: &Budget,
+        diagnostics: &Diagnostics,
+    ) -> Result<Vec<SearchResult>> {
+        if options.scope.is_some() && options.mode == MatchMode::Inverted {
+            return Err(anyhow!(
+                "Inverted matching works on lines, it can't be limited to comments, strings or code"
+            ));
+        }
+        let sources_list = &config.config_map.sources;
+        let matchers = sources_list
+            .iter()
+            .map(|source_path| Matcher::new(options, config.get_analyzer(source_path)))
+            .collect::<Result<Vec<Matcher>>>()?;
+
+        Ok(sources_list
+            .par_iter()
+            .zip(matchers.par_iter())
+            .flat_map(|(source_path, matcher)| {
+                Walker::source_files_within(
+                    source_path,
+                    options.git_ref.as_deref(),
+                    budget,
+                    diagnostics,
+                )
+                .filter_map(|source_file| counters.count(source_file))
+                .filter_map(move |source_file| {
+                    let path = source_file.path.clone();
+                    let (contents, matches) = match (&options.log, options.scope) {
+                        (Some(time_range), _) => {
+                            Self::search_log(options, matcher, time_range, &source_file.contents)?
                         }
+                        (None, Some(scope)) => {
+                            Self::search_scope(options, matcher, scope, source_file)?
+                        }
+                        (None, None) => options.mode.apply(matcher, source_file.contents)?,
                     };
-                    let mut file_contents = String::new();
-                    match file.read_to_string(&mut file_contents) {
-                        Ok(_) => {}
-                        Err(error) => {
-                            eprintln!(
-                                "Unable to read file: {:?}, due to {:?}",
-                                dir_entry.path(),
-                                error
-                            );
-                            return String::new();
+                    Some(SearchResult {
+                        source: source_path.clone(),
+                        path,
+                        contents,
+                        bytes: None,
+                        matches,
+                    })
+                })
+            })
+            .inspect(|result| budget.count(result))
+            .collect())
+    }
+
+    /// Only the entries within the time range are searched, and a match is
+    /// shown with the rest of its entry
+    fn search_log(
+        options: &SearchOptions,
+        matcher: &Matcher,
+        time_range: &TimeRange,
+        contents: &str,
+    ) -> Option<(String, Vec<Match>)> {
+        let contents = time_range.filter(contents)?;
+        if options.patterns.is_empty() {
+            return Some((contents, Vec::new()));
+        }
+
+        let (contents, matches) = options.mode.apply(matcher, contents)?;
+        Some(match options.mode {
+            MatchMode::Matching => logs::matching_entries(&contents, matches),
+            _ => (contents, matches),
+        })
+    }
+
+    /// Matches are kept when they're in the part of the file searched,
+    /// files without a syntax tree can't tell their parts apart
+    fn search_scope(
+        options: &SearchOptions,
+        matcher: &Matcher,
+        scope: SyntaxScope,
+        source_file: SourceFile,
+    ) -> Option<(String, Vec<Match>)> {
+        let literals = Syntax::from_path(&source_file.path)?.literals(&source_file.contents)?;
+        let matches = matcher
+            .find(&source_file.contents)
+            .into_iter()
+            .filter(|found| scope.contains(&literals, found.start..found.end))
+            .collect::<Vec<Match>>();
+
+        match options.mode {
+            MatchMode::Matching if !matches.is_empty() => Some((source_file.contents, matches)),
+            MatchMode::FilesWithoutMatch if matches.is_empty() => {
+                Some((source_file.contents, matches))
+            }
+            _ => None,
+        }
+    }
+
+    fn search_structured(
+        config: &Config,
+        options: &SearchOptions,
+        structured_query: &StructuredQuery,
+        counters: &Counters,
+        budget: &Budget,
+        diagnostics: &Diagnostics,
+    ) -> Result<Vec<SearchResult>> {
+        if options.mode == MatchMode::Inverted {
+            return Err(anyhow!(
+                "Inverted matching works on lines, it can't be used with structured queries"
+            ));
+        }
+
+        Ok(config
+            .config_map
+            .sources
+            .par_iter()
+            .flat_map(|source_path| {
+                Walker::source_files_within(
+                    source_path,
+                    options.git_ref.as_deref(),
+                    budget,
+                    diagnostics,
+                )
+                .filter_map(|source_file| counters.count(source_file))
+                .filter_map(move |source_file| {
+                    let document =
+                        match structured::parse_document(&source_file.path, &source_file.contents)?
+                        {
+                            Ok(document) => document,
+                            Err(error) => {
+                                diagnostics::report(
+                                    diagnostics,
+                                    DiagnosticKind::ParseError,
+                                    &source_file.path,
+                                    error,
+                                );
+                                return None;
+                            }
+                        };
+                    let (contents, matches) = structured_query.find(&document);
+                    let (contents, matches) = match options.mode {
+                        MatchMode::FilesWithoutMatch if matches.is_empty() => {
+                            (source_file.contents, matches)
                         }
+                        MatchMode::Matching if !matches.is_empty() => (contents, matches),
+                        _ => return None,
                     };
-                    file_contents
+                    Some(SearchResult {
+                        source: source_path.clone(),
+                        path: source_file.path,
+                        contents,
+                        bytes: None,
+                        matches,
+                    })
                 })
-                .filter(|file_contents| file_contents.contains(&term))
-                .collect::<Vec<String>>();
+            })
+            .inspect(|result| budget.count(result))
+            .collect())
+    }
 
-            Ok(files_with_term)
-        } else {
-            panic!("No available sources to search through")
+    fn search_bytes(
+        config: &Config,
+        options: &SearchOptions,
+        counters: &Counters,
+        budget: &Budget,
+        diagnostics: &Diagnostics,
+    ) -> Result<Vec<SearchResult>> {
+        if options.mode == MatchMode::Inverted {
+            return Err(anyhow!(
+                "Inverted matching works on lines, it can't be used with byte patterns"
+            ));
         }
+        let patterns = options
+            .patterns
+            .iter()
+            .map(|pattern| pattern.as_bytes().to_vec())
+            .chain(options.byte_patterns.iter().cloned())
+            .collect::<Vec<Vec<u8>>>();
+        let matcher = ByteMatcher::new(&patterns);
+
+        Ok(config
+            .config_map
+            .sources
+            .par_iter()
+            .flat_map(|source_path| {
+                let matcher = &matcher;
+                Walker::source_bytes_within(
+                    source_path,
+                    options.git_ref.as_deref(),
+                    budget,
+                    diagnostics,
+                )
+                .filter_map(|source_file| counters.count(source_file))
+                .filter_map(move |source_file| {
+                    let matches = matcher.apply(&options.mode, &source_file.contents)?;
+                    Some(SearchResult {
+                        source: source_path.clone(),
+                        path: source_file.path,
+                        contents: String::new(),
+                        bytes: Some(source_file.contents),
+                        matches,
+                    })
+                })
+            })
+            .inspect(|result| budget.count(result))
+            .collect())
     }
 }
 
@@ -67,8 +426,9 @@ mod tests {
     use super::*;
     use anyhow::Result;
     use config::Configuration;
+    use std::time::Duration;
     use tempfile::tempdir;
-    use test_utils::create_fake_source;
+    use test_utils::{create_fake_git_remote, create_fake_git_source, create_fake_source};
 
     #[test]
     fn it_returns_the_results() -> Result<()> {
@@ -96,6 +456,394 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn it_searches_a_git_source_at_a_given_ref() -> Result<()> {
+        let source = create_fake_git_source()?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let options = SearchOptions {
+            patterns: vec!["released".to_string()],
+            git_ref: Some("v1".to_string()),
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].contents, "released.test");
+        assert_eq!(result.results[0].path, source.path().join("file.txt"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_only_searches_refs_of_the_remote_once_fetched() -> Result<()> {
+        let (_origin, clone) = create_fake_git_remote()?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(clone.path())?;
+        let options = SearchOptions {
+            patterns: vec!["feature.test".to_string()],
+            git_ref: Some("feature".to_string()),
+            ..SearchOptions::default()
+        };
+
+        let before_fetching = Search::with_options(&config, &options)?;
+        assert!(before_fetching.results.is_empty());
+        assert_eq!(
+            before_fetching.diagnostics[0].kind,
+            DiagnosticKind::UnreadableSource
+        );
+
+        Search::fetch(&config, "feature")?;
+        let fetched = Search::with_options(&config, &options)?;
+        let mut paths = fetched
+            .results
+            .iter()
+            .map(|result| result.path.clone())
+            .collect::<Vec<PathBuf>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                clone.path().join("feature.txt"),
+                clone.path().join("other.txt")
+            ]
+        );
+        assert!(Search::fetch(&config, "--upload-pack=touch").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_skips_sources_that_are_not_git_repositories_when_searching_a_ref() -> Result<()> {
+        let source = create_fake_source()?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let options = SearchOptions {
+            patterns: vec!["d.txt.test".to_string()],
+            git_ref: Some("v1".to_string()),
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.results, Vec::<SearchResult>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn it_returns_the_files_without_the_term() -> Result<()> {
+        let source = create_fake_source()?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let options = SearchOptions {
+            patterns: vec!["txt".to_string()],
+            mode: MatchMode::FilesWithoutMatch,
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.results, Vec::<SearchResult>::new());
+
+        let options = SearchOptions {
+            patterns: vec!["d.txt".to_string()],
+            mode: MatchMode::FilesWithoutMatch,
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.results.len(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn it_tags_the_matches_with_the_pattern_found() -> Result<()> {
+        let source = create_fake_source()?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let options = SearchOptions {
+            patterns: vec!["e.txt".to_string(), "f.txt".to_string()],
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.results.len(), 6);
+        for search_result in result.results {
+            let expected_pattern = match search_result.contents.as_str() {
+                "e.txt.test" => 0,
+                _ => 1,
+            };
+            assert_eq!(search_result.matches.len(), 1);
+            assert_eq!(search_result.matches[0].pattern, expected_pattern);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_uses_the_analyzer_of_each_source() -> Result<()> {
+        let prose_source = tempdir()?;
+        std::fs::write(prose_source.path().join("notes.md"), "Deployed on friday")?;
+        let code_source = tempdir()?;
+        std::fs::write(code_source.path().join("main.rs"), "fn deployed() {}")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(prose_source.path())?;
+        config.add_source(code_source.path())?;
+        config.set_analyzer(prose_source.path(), Some(config::Language::English))?;
+
+        let result = Search::by_term(&config, "deploying".to_string())?;
+
+        assert_eq!(result, vec!["Deployed on friday"]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_suggests_close_terms_when_nothing_matches() -> Result<()> {
+        let source = tempdir()?;
+        std::fs::write(
+            source.path().join("config.toml"),
+            "timeout = 30\nretries = 3",
+        )?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let options = SearchOptions {
+            patterns: vec!["timeuot".to_string()],
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.results, Vec::<SearchResult>::new());
+        assert_eq!(result.suggestions.len(), 1);
+        assert_eq!(result.suggestions[0].term, "timeout");
+
+        let options = SearchOptions {
+            patterns: vec!["timeout".to_string()],
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.suggestions, Vec::<Suggestion>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn it_finds_byte_patterns_in_binary_files() -> Result<()> {
+        let source = tempdir()?;
+        std::fs::write(
+            source.path().join("firmware.bin"),
+            [0x00, 0xff, 0xde, 0xad, 0xbe, 0xef],
+        )?;
+        std::fs::write(source.path().join("notes.txt"), "no magic here")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let options = SearchOptions {
+            byte_patterns: vec![parse_hex("de ad be ef")?],
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].path, source.path().join("firmware.bin"));
+        assert_eq!(
+            (
+                result.results[0].matches[0].start,
+                result.results[0].matches[0].end
+            ),
+            (2, 6)
+        );
+        assert_eq!(
+            result.results[0].bytes,
+            Some(vec![0x00, 0xff, 0xde, 0xad, 0xbe, 0xef])
+        );
+        assert_eq!(result.stats.files_skipped, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn it_searches_the_nodes_of_structured_files() -> Result<()> {
+        let source = tempdir()?;
+        std::fs::write(
+            source.path().join("api.json"),
+            r#"{"timeout": 45, "retries": 3}"#,
+        )?;
+        std::fs::write(source.path().join("worker.toml"), "timeout = 10")?;
+        std::fs::write(source.path().join("notes.txt"), "timeout = 50")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let options = SearchOptions {
+            structured: Some(StructuredQuery::parse("key:timeout value:>30")?),
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].path, source.path().join("api.json"));
+        assert_eq!(result.results[0].contents, ".timeout = 45");
+        Ok(())
+    }
+
+    #[test]
+    fn it_searches_log_entries_within_a_time_range() -> Result<()> {
+        let source = tempdir()?;
+        std::fs::write(
+            source.path().join("app.log"),
+            "2026-10-01 09:00:00 ERROR timeout\n\
+             2026-10-01 10:30:00 ERROR crashed\n\
+             \tat main.rs:12\n\
+             2026-10-01 10:45:00 INFO recovered\n",
+        )?;
+        std::fs::write(source.path().join("notes.txt"), "ERROR in the notes")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let options = SearchOptions {
+            patterns: vec!["ERROR".to_string()],
+            log: Some(TimeRange::parse(Some("2026-10-01T10:00"), None)?),
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(
+            result.results[0].contents,
+            "2026-10-01 10:30:00 ERROR crashed\n\tat main.rs:12"
+        );
+        assert_eq!(result.results[0].matches.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn it_skips_what_is_over_the_limits_and_reports_them() -> Result<()> {
+        let source = tempdir()?;
+        let nested = source.path().join("nested");
+        std::fs::create_dir(&nested)?;
+        std::fs::write(source.path().join("small.txt"), "needle")?;
+        std::fs::write(source.path().join("large.txt"), "needle ".repeat(100))?;
+        std::fs::write(nested.join("deep.txt"), "needle")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let options = SearchOptions {
+            patterns: vec!["needle".to_string()],
+            limits: Limits {
+                max_file_size: Some(100),
+                max_depth: Some(1),
+                ..Limits::default()
+            },
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].path, source.path().join("small.txt"));
+        assert_eq!(
+            result.stats.limits_hit,
+            vec![Limit::MaxFileSize, Limit::MaxDepth]
+        );
+        assert!(result.suggestions.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn it_stops_going_into_directories_once_the_time_is_spent() -> Result<()> {
+        let source = tempdir()?;
+        // Only directories, so there's no file the walk could stop at
+        std::fs::create_dir_all(source.path().join("a").join("b").join("c"))?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let options = SearchOptions {
+            patterns: vec!["needle".to_string()],
+            limits: Limits {
+                timeout: Some(Duration::ZERO),
+                ..Limits::default()
+            },
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert!(result.results.is_empty());
+        assert_eq!(result.stats.limits_hit, vec![Limit::Timeout]);
+        Ok(())
+    }
+
+    #[test]
+    fn it_returns_what_it_could_not_search_as_diagnostics() -> Result<()> {
+        let source = tempdir()?;
+        std::fs::write(source.path().join("api.json"), "{ \"timeout\": 45 }")?;
+        std::fs::write(source.path().join("broken.json"), "{ \"timeout\": ")?;
+        std::fs::write(source.path().join("image.bin"), [0xff, 0xfe, 0x00])?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let options = SearchOptions {
+            structured: Some(StructuredQuery::parse("key:timeout")?),
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.results.len(), 1);
+        let diagnostics = result
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.kind, diagnostic.path.clone()))
+            .collect::<Vec<(DiagnosticKind, PathBuf)>>();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (DiagnosticKind::NotUtf8, source.path().join("image.bin")),
+                (
+                    DiagnosticKind::ParseError,
+                    source.path().join("broken.json")
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_searches_only_the_comments_strings_or_code() -> Result<()> {
+        let source = tempdir()?;
+        std::fs::write(
+            source.path().join("main.py"),
+            "# TODO: retry\nprint(\"TODO\")\nTODO = 1\n",
+        )?;
+        std::fs::write(source.path().join("notes.txt"), "TODO")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+        let options = SearchOptions {
+            patterns: vec!["TODO".to_string()],
+            scope: Some(SyntaxScope::Comments),
+            ..SearchOptions::default()
+        };
+
+        let result = Search::with_options(&config, &options)?;
+
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.results[0].path, source.path().join("main.py"));
+        assert_eq!(result.results[0].matches.len(), 1);
+        assert_eq!(result.results[0].matches[0].start, 2);
+        Ok(())
+    }
+
     #[test]
     #[should_panic(expected = "No available sources to search through")]
     fn it_panics_when_there_are_no_sources() {
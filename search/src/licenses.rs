@@ -0,0 +1,362 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use config::Config;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    diagnostics::{self, Diagnostic, DiagnosticKind, Diagnostics},
+    walker::Walker,
+};
+
+const SPDX_TAG: &str = "SPDX-License-Identifier:";
+/// SPDX headers are expected near the top of the file
+const HEADER_LINES: usize = 20;
+/// Names of license files, which may be followed by `-<anything>` like in `LICENSE-MIT`
+const LICENSE_FILE_NAMES: &[&str] = &["license", "licence", "copying", "unlicense"];
+const LICENSE_FILE_EXTENSIONS: &[&str] = &[".md", ".txt"];
+/// Files that are expected to carry an SPDX header
+const CODE_EXTENSIONS: &[&str] = &[
+    "c", "cc", "cpp", "cs", "go", "h", "hpp", "java", "js", "jsx", "kt", "php", "py", "rb", "rs",
+    "scala", "sh", "swift", "ts", "tsx",
+];
+
+/// Phrases that tell licenses apart, the first license with all of its phrases wins
+const LICENSE_TEXTS: &[(&str, &[&str])] = &[
+    ("AGPL-3.0", &["GNU AFFERO GENERAL PUBLIC LICENSE"]),
+    (
+        "LGPL-3.0",
+        &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 3"],
+    ),
+    (
+        "LGPL-2.1",
+        &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 2.1"],
+    ),
+    ("GPL-3.0", &["GNU GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("GPL-2.0", &["GNU GENERAL PUBLIC LICENSE", "Version 2"]),
+    ("Apache-2.0", &["Apache License", "Version 2.0"]),
+    ("MPL-2.0", &["Mozilla Public License", "2.0"]),
+    (
+        "BSD-3-Clause",
+        &[
+            "Redistribution and use in source and binary forms",
+            "Neither the name",
+        ],
+    ),
+    (
+        "BSD-2-Clause",
+        &["Redistribution and use in source and binary forms"],
+    ),
+    ("MIT", &["Permission is hereby granted, free of charge"]),
+    (
+        "ISC",
+        &["Permission to use, copy, modify, and/or distribute this software for any purpose"],
+    ),
+    (
+        "Unlicense",
+        &["This is free and unencumbered software released into the public domain"],
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LicenseFile {
+    pub path: PathBuf,
+    /// SPDX identifier of the license, `unknown` when the text isn't recognized
+    pub license: String,
+    /// Found in a subdirectory, like third party code kept in the source
+    pub vendored: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LicenseReport {
+    pub source: PathBuf,
+    pub license_files: Vec<LicenseFile>,
+    /// How many files declare each license in their SPDX header
+    pub headers: BTreeMap<String, usize>,
+    /// Code files without an SPDX header, vendored code left out
+    pub missing_headers: Vec<PathBuf>,
+    /// Files of the source that couldn't be read
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+enum Found {
+    LicenseFile(PathBuf, String),
+    Header(String),
+    MissingHeader(PathBuf),
+}
+
+/// Reports the licenses of each source, from its license files and the SPDX
+/// headers of its code
+pub struct Licenses;
+
+impl Licenses {
+    pub fn find(config: &Config) -> Vec<LicenseReport> {
+        config
+            .config_map
+            .sources
+            .iter()
+            .map(|source_path| Self::report(source_path))
+            .collect()
+    }
+
+    fn report(source_path: &Path) -> LicenseReport {
+        let diagnostics = Diagnostics::default();
+        let found = Walker::source_paths_kept(source_path, is_checked, &diagnostics)
+            .filter_map(|path| {
+                if is_license_file(&path) {
+                    let license = identify(&read_lines(&path, usize::MAX, &diagnostics)?);
+                    return Some(Found::LicenseFile(path, license));
+                }
+                let header = read_lines(&path, HEADER_LINES, &diagnostics)?;
+                Some(match spdx_header(&header) {
+                    Some(license) => Found::Header(license),
+                    None => Found::MissingHeader(path),
+                })
+            })
+            .collect::<Vec<Found>>();
+
+        let mut license_files = Vec::new();
+        let mut headers = BTreeMap::new();
+        let mut missing_headers = Vec::new();
+        for found in found {
+            match found {
+                Found::LicenseFile(path, license) => license_files.push(LicenseFile {
+                    vendored: path.parent() != Some(source_path),
+                    path,
+                    license,
+                }),
+                Found::Header(license) => *headers.entry(license).or_default() += 1,
+                Found::MissingHeader(path) => missing_headers.push(path),
+            }
+        }
+
+        let vendored_directories = license_files
+            .iter()
+            .filter(|license_file| license_file.vendored)
+            .filter_map(|license_file| license_file.path.parent())
+            .collect::<Vec<&Path>>();
+        missing_headers.retain(|path: &PathBuf| {
+            !vendored_directories
+                .iter()
+                .any(|directory| path.starts_with(directory))
+        });
+        missing_headers.sort();
+        license_files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        LicenseReport {
+            source: source_path.to_path_buf(),
+            license_files,
+            headers,
+            missing_headers,
+            diagnostics: diagnostics.into_sorted(),
+        }
+    }
+}
+
+/// Only license files and code files are read
+fn is_checked(name: &str, is_dir: bool) -> bool {
+    let path = Path::new(name);
+    is_dir || is_license_file(path) || is_code(path)
+}
+
+/// First lines of a file. Bytes that aren't UTF-8 are replaced, license texts
+/// and SPDX tags are plain ASCII.
+fn read_lines(path: &Path, max_lines: usize, diagnostics: &Diagnostics) -> Option<String> {
+    let report = |error: std::io::Error| {
+        diagnostics::report(
+            diagnostics,
+            DiagnosticKind::of_io_error(&error),
+            path,
+            error,
+        )
+    };
+    let mut reader = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(error) => {
+            report(error);
+            return None;
+        }
+    };
+    let mut contents = Vec::new();
+    for _ in 0..max_lines {
+        match reader.read_until(b'\n', &mut contents) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(error) => {
+                report(error);
+                return None;
+            }
+        }
+    }
+
+    Some(String::from_utf8_lossy(&contents).into_owned())
+}
+
+fn is_license_file(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name.to_lowercase(),
+        None => return false,
+    };
+
+    let stem = LICENSE_FILE_EXTENSIONS
+        .iter()
+        .find_map(|extension| name.strip_suffix(extension))
+        .unwrap_or(&name);
+    if stem.contains('.') {
+        return false;
+    }
+
+    LICENSE_FILE_NAMES
+        .iter()
+        .any(|license_name| match stem.strip_prefix(license_name) {
+            Some(rest) => rest.is_empty() || rest.starts_with('-'),
+            None => false,
+        })
+}
+
+fn is_code(path: &Path) -> bool {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => CODE_EXTENSIONS.contains(&extension.to_lowercase().as_str()),
+        None => false,
+    }
+}
+
+/// License of a license file, from its SPDX tag or its text
+fn identify(contents: &str) -> String {
+    if let Some(license) = spdx_header(contents) {
+        return license;
+    }
+    let text = contents.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    LICENSE_TEXTS
+        .iter()
+        .find(|(_, phrases)| phrases.iter().all(|phrase| text.contains(phrase)))
+        .map_or_else(
+            || String::from("unknown"),
+            |(license, _)| license.to_string(),
+        )
+}
+
+fn spdx_header(contents: &str) -> Option<String> {
+    contents.lines().take(HEADER_LINES).find_map(|line| {
+        let (_, expression) = line.split_once(SPDX_TAG)?;
+        let expression = expression
+            .trim()
+            .trim_end_matches("*/")
+            .trim_end_matches("-->")
+            .trim();
+        Some(expression.to_string()).filter(|expression| !expression.is_empty())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use anyhow::Result;
+    use config::Configuration;
+    use tempfile::tempdir;
+
+    const MIT: &str = "MIT License\n\nPermission is hereby granted, free of charge, to any person\nobtaining a copy of this software";
+    const APACHE: &str = "                              Apache License\n                        Version 2.0, January 2004";
+
+    #[test]
+    fn it_identifies_licenses_by_their_text_or_tag() {
+        assert_eq!(identify(MIT), "MIT");
+        assert_eq!(identify(APACHE), "Apache-2.0");
+        assert_eq!(
+            identify("GNU GENERAL PUBLIC LICENSE\nVersion 2, June 1991"),
+            "GPL-2.0"
+        );
+        assert_eq!(identify("All rights reserved."), "unknown");
+        assert_eq!(
+            spdx_header("/* SPDX-License-Identifier: MIT OR Apache-2.0 */\nint main;"),
+            Some(String::from("MIT OR Apache-2.0"))
+        );
+        assert_eq!(spdx_header("fn main() {}"), None);
+    }
+
+    #[test]
+    fn it_only_reads_the_header_lines_of_code_files() -> Result<()> {
+        let source = tempdir()?;
+        let code_path = source.path().join("main.rs");
+        let late_tag = format!(
+            "{}// SPDX-License-Identifier: MIT\n",
+            "fn main() {}\n".repeat(HEADER_LINES)
+        );
+        fs::write(&code_path, late_tag)?;
+        let diagnostics = Diagnostics::default();
+
+        let header = read_lines(&code_path, HEADER_LINES, &diagnostics).unwrap();
+
+        assert_eq!(header.lines().count(), HEADER_LINES);
+        assert_eq!(spdx_header(&header), None);
+        assert!(!is_checked("README.md", false));
+        assert!(is_checked("main.rs", false));
+        assert!(is_checked("docs", true));
+        Ok(())
+    }
+
+    #[test]
+    fn it_only_takes_the_known_names_as_license_files() {
+        for name in &[
+            "LICENSE",
+            "LICENSE-MIT",
+            "licence.txt",
+            "COPYING.md",
+            "UNLICENSE",
+        ] {
+            assert!(is_license_file(Path::new(name)), "{}", name);
+        }
+        for name in &[
+            "licenses.rs",
+            "license_test.go",
+            "LicenseController.java",
+            "copying.c",
+            "LICENSE-check.sh",
+        ] {
+            assert!(!is_license_file(Path::new(name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn it_reports_license_files_headers_and_files_missing_them() -> Result<()> {
+        let source = tempdir()?;
+        let vendored = source.path().join("vendor/left-pad");
+        fs::create_dir_all(&vendored)?;
+        fs::write(source.path().join("LICENSE-MIT"), MIT)?;
+        fs::write(vendored.join("LICENSE.txt"), APACHE)?;
+        fs::write(vendored.join("index.js"), "module.exports = pad;")?;
+        fs::write(
+            source.path().join("main.rs"),
+            "// SPDX-License-Identifier: MIT\nfn main() {}",
+        )?;
+        fs::write(source.path().join("lib.rs"), "pub fn run() {}")?;
+        fs::write(source.path().join("README.md"), "# Docs")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+
+        let reports = Licenses::find(&config);
+
+        assert_eq!(reports.len(), 1);
+        let licenses = reports[0]
+            .license_files
+            .iter()
+            .map(|license_file| (license_file.license.as_str(), license_file.vendored))
+            .collect::<Vec<(&str, bool)>>();
+        assert_eq!(licenses, vec![("MIT", false), ("Apache-2.0", true)]);
+        assert_eq!(reports[0].headers.get("MIT"), Some(&1));
+        assert_eq!(
+            reports[0].missing_headers,
+            vec![source.path().join("lib.rs")]
+        );
+        Ok(())
+    }
+}
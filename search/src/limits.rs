@@ -0,0 +1,341 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::SearchResult;
+
+/// Bounds on how much a search reads and returns. `Limits::default()` sets none
+/// of them, while the command line caps the file size and the time by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Files with matches to stop at
+    pub max_results: Option<usize>,
+    /// Matches to stop at, the last file keeps the ones up to the limit
+    pub max_matches: Option<usize>,
+    /// Files to read before stopping
+    pub max_files: Option<usize>,
+    /// Files larger than this, in bytes, are skipped
+    pub max_file_size: Option<u64>,
+    /// How deep to walk each source, 1 only reads the files at its root
+    pub max_depth: Option<usize>,
+    /// Time the whole search can take, it stops reading files after that
+    pub timeout: Option<Duration>,
+}
+
+/// A limit that cut a search short
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Limit {
+    MaxResults,
+    MaxMatches,
+    MaxFiles,
+    MaxFileSize,
+    MaxDepth,
+    Timeout,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limit = match self {
+            Limit::MaxResults => "max results",
+            Limit::MaxMatches => "max matches",
+            Limit::MaxFiles => "max files",
+            Limit::MaxFileSize => "max file size",
+            Limit::MaxDepth => "max depth",
+            Limit::Timeout => "timeout",
+        };
+        write!(f, "{}", limit)
+    }
+}
+
+/// Reads a size in bytes, with an optional K, M or G suffix like `512K` or `10M`
+pub fn parse_size(size: &str) -> Result<u64> {
+    let size = size.trim();
+    let (digits, multiplier) = match size.char_indices().last() {
+        Some((index, 'k')) | Some((index, 'K')) => (&size[..index], 1 << 10),
+        Some((index, 'm')) | Some((index, 'M')) => (&size[..index], 1 << 20),
+        Some((index, 'g')) | Some((index, 'G')) => (&size[..index], 1 << 30),
+        _ => (size, 1),
+    };
+    let value = digits.parse::<u64>().map_err(|_| {
+        anyhow!(
+            "Invalid size: {}, use a number of bytes like 512K or 10M",
+            size
+        )
+    })?;
+
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| anyhow!("Size is too large: {}", size))
+}
+
+/// Keeps track of the limits while the search runs across the thread pool
+#[derive(Debug)]
+pub(crate) struct Budget {
+    limits: Limits,
+    started_at: Instant,
+    files: AtomicUsize,
+    results: AtomicUsize,
+    matches: AtomicUsize,
+    hit: Mutex<BTreeSet<Limit>>,
+}
+
+impl Budget {
+    pub(crate) fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            started_at: Instant::now(),
+            files: AtomicUsize::new(0),
+            results: AtomicUsize::new(0),
+            matches: AtomicUsize::new(0),
+            hit: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    pub(crate) fn max_depth(&self) -> Option<usize> {
+        self.limits.max_depth
+    }
+
+    /// Whether another file can be read, once one can't the walk stops
+    pub(crate) fn take_file(&self) -> bool {
+        if self.is_out_of_time() {
+            self.hit(Limit::Timeout);
+            return false;
+        }
+        if Self::is_reached(&self.results, self.limits.max_results) {
+            self.hit(Limit::MaxResults);
+            return false;
+        }
+        if Self::is_reached(&self.matches, self.limits.max_matches) {
+            self.hit(Limit::MaxMatches);
+            return false;
+        }
+        match self.limits.max_files {
+            Some(max_files) if self.files.fetch_add(1, Ordering::Relaxed) >= max_files => {
+                self.hit(Limit::MaxFiles);
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether the time of the search is spent
+    pub(crate) fn is_out_of_time(&self) -> bool {
+        match self.limits.timeout {
+            Some(timeout) => self.started_at.elapsed() >= timeout,
+            None => false,
+        }
+    }
+
+    /// When the time of the search is spent, for what can't hold on to the budget
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.started_at.checked_add(self.limits.timeout?)
+    }
+
+    /// Whether a file is small enough to be read
+    pub(crate) fn fits(&self, size: u64) -> bool {
+        match self.limits.max_file_size {
+            Some(max_file_size) if size > max_file_size => {
+                self.hit(Limit::MaxFileSize);
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Notes a directory the walk doesn't go into, either at the max depth or
+    /// reached once the time is spent
+    pub(crate) fn leave_out(&self, depth: usize, directory: &Path) {
+        if self.is_out_of_time() {
+            self.hit(Limit::Timeout);
+            return;
+        }
+        if !matches!(self.limits.max_depth, Some(max_depth) if depth >= max_depth) {
+            return;
+        }
+        let is_empty = match directory.read_dir() {
+            Ok(mut entries) => entries.next().is_none(),
+            Err(_) => true,
+        };
+        if !is_empty {
+            self.hit(Limit::MaxDepth);
+        }
+    }
+
+    /// Drops the files of a revision deeper than the max depth
+    pub(crate) fn keep_within_depth(&self, files: &mut Vec<PathBuf>) {
+        if let Some(max_depth) = self.limits.max_depth {
+            let file_count = files.len();
+            files.retain(|file_path| file_path.components().count() <= max_depth);
+            if files.len() < file_count {
+                self.hit(Limit::MaxDepth);
+            }
+        }
+    }
+
+    pub(crate) fn count(&self, result: &SearchResult) {
+        self.results.fetch_add(1, Ordering::Relaxed);
+        self.matches
+            .fetch_add(result.matches.len(), Ordering::Relaxed);
+    }
+
+    /// Files are matched in parallel, so a few more results than the limits
+    /// allow can come in before the walk stops. They're cut here. A limit is
+    /// only reported when it stopped the walk or dropped results or matches.
+    pub(crate) fn finish(self, results: &mut Vec<SearchResult>) -> Vec<Limit> {
+        let mut hit = self
+            .hit
+            .into_inner()
+            .unwrap_or_else(|error| error.into_inner());
+        if let Some(max_results) = self.limits.max_results {
+            if results.len() > max_results {
+                results.truncate(max_results);
+                hit.insert(Limit::MaxResults);
+            }
+        }
+        if let Some(max_matches) = self.limits.max_matches {
+            let match_count = Self::match_count(results);
+            let mut remaining = max_matches;
+            let mut kept = 0;
+            for result in results.iter_mut() {
+                if remaining == 0 && !result.matches.is_empty() {
+                    break;
+                }
+                result.matches.truncate(remaining);
+                remaining -= result.matches.len();
+                kept += 1;
+            }
+            results.truncate(kept);
+            if Self::match_count(results) < match_count {
+                hit.insert(Limit::MaxMatches);
+            }
+        }
+
+        hit.into_iter().collect()
+    }
+
+    fn match_count(results: &[SearchResult]) -> usize {
+        results.iter().map(|result| result.matches.len()).sum()
+    }
+
+    fn is_reached(count: &AtomicUsize, limit: Option<usize>) -> bool {
+        match limit {
+            Some(limit) => count.load(Ordering::Relaxed) >= limit,
+            None => false,
+        }
+    }
+
+    fn hit(&self, limit: Limit) {
+        if let Ok(mut hit) = self.hit.lock() {
+            hit.insert(limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Match;
+
+    fn search_result(matches: usize) -> SearchResult {
+        let found = Match {
+            pattern: 0,
+            start: 0,
+            end: 0,
+            start_line: 1,
+            end_line: 1,
+        };
+        SearchResult {
+            source: PathBuf::from("source"),
+            path: PathBuf::from("source/file.txt"),
+            contents: String::new(),
+            bytes: None,
+            matches: vec![found; matches],
+        }
+    }
+
+    #[test]
+    fn it_cuts_the_results_and_matches_over_the_limits() {
+        let budget = Budget::new(Limits {
+            max_results: Some(3),
+            max_matches: Some(4),
+            ..Limits::default()
+        });
+        let mut results = vec![
+            search_result(2),
+            search_result(3),
+            search_result(1),
+            search_result(1),
+        ];
+
+        let hit = budget.finish(&mut results);
+
+        let matches = results
+            .iter()
+            .map(|result| result.matches.len())
+            .collect::<Vec<usize>>();
+        assert_eq!(matches, vec![2, 2]);
+        assert_eq!(hit, vec![Limit::MaxResults, Limit::MaxMatches]);
+    }
+
+    #[test]
+    fn it_only_reports_the_limits_that_dropped_something() {
+        let budget = Budget::new(Limits {
+            max_results: Some(2),
+            max_matches: Some(3),
+            ..Limits::default()
+        });
+        let mut results = vec![search_result(2), search_result(1)];
+
+        assert_eq!(budget.finish(&mut results), Vec::<Limit>::new());
+        assert_eq!(results.len(), 2);
+
+        let budget = Budget::new(Limits {
+            max_results: Some(1),
+            ..Limits::default()
+        });
+        budget.count(&search_result(1));
+        assert!(!budget.take_file());
+        assert_eq!(budget.finish(&mut Vec::new()), vec![Limit::MaxResults]);
+    }
+
+    #[test]
+    fn it_parses_sizes_with_a_suffix() -> Result<()> {
+        assert_eq!(parse_size("512")?, 512);
+        assert_eq!(parse_size("4k")?, 4096);
+        assert_eq!(parse_size("10M")?, 10 * 1024 * 1024);
+        assert_eq!(parse_size("1G")?, 1 << 30);
+        assert!(parse_size("ten").is_err());
+        assert!(parse_size("M").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_stops_taking_files_past_the_limits() {
+        let budget = Budget::new(Limits {
+            max_files: Some(2),
+            max_file_size: Some(10),
+            ..Limits::default()
+        });
+
+        assert!(budget.take_file());
+        assert!(budget.take_file());
+        assert!(!budget.take_file());
+        assert!(budget.fits(10));
+        assert!(!budget.fits(11));
+        assert_eq!(
+            budget.finish(&mut Vec::new()),
+            vec![Limit::MaxFiles, Limit::MaxFileSize]
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use std::ops::Range;
+
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::Match;
+
+/// Fraction digits past nanoseconds are ignored
+const MAX_FRACTION_DIGITS: usize = 9;
+
+/// Limits log entries to the ones timestamped from `since` up to, but not
+/// including, `until`. Timestamps with an offset are compared in UTC, the
+/// ones without are taken as they're written.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimeRange {
+    since: Option<NaiveDateTime>,
+    until: Option<NaiveDateTime>,
+}
+
+impl TimeRange {
+    /// Bounds written like `2026-10-01`, `2026-10-01T10:00` or `2026-10-01 10:00:00+01:00`
+    pub fn parse(since: Option<&str>, until: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            since: since.map(parse_bound).transpose()?,
+            until: until.map(parse_bound).transpose()?,
+        })
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+
+    fn contains(&self, timestamp: NaiveDateTime) -> bool {
+        let after_since = match self.since {
+            Some(since) => timestamp >= since,
+            None => true,
+        };
+        let before_until = match self.until {
+            Some(until) => timestamp < until,
+            None => true,
+        };
+        after_since && before_until
+    }
+
+    /// The entries of a log timestamped within the range, one after the other.
+    /// `None` if nothing is, or if the file isn't a log at all.
+    pub(crate) fn filter(&self, contents: &str) -> Option<String> {
+        let entries = entries(contents);
+        if entries.iter().all(|entry| entry.timestamp.is_none()) {
+            return None;
+        }
+
+        let kept = entries
+            .iter()
+            .filter(|entry| matches!(entry.timestamp, Some(timestamp) if self.contains(timestamp)))
+            .map(|entry| &contents[entry.range.clone()])
+            .collect::<Vec<&str>>();
+        Some(kept.join("\n")).filter(|_| !kept.is_empty())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Entry {
+    /// Lines of the entry, without the line break that ends it
+    range: Range<usize>,
+    timestamp: Option<NaiveDateTime>,
+}
+
+/// Splits a log into entries, each starting at a timestamped line. Lines
+/// without a timestamp, like the frames of a stack trace, belong to the entry
+/// above them.
+fn entries(contents: &str) -> Vec<Entry> {
+    let mut entries = Vec::<Entry>::new();
+    let mut offset = 0;
+    for line in contents.split_inclusive('\n') {
+        let timestamp = line_timestamp(line);
+        let end = offset + line.trim_end_matches(&['\n', '\r'][..]).len();
+        match entries.last_mut() {
+            Some(entry) if timestamp.is_none() => entry.range.end = end,
+            _ => entries.push(Entry {
+                range: offset..end,
+                timestamp,
+            }),
+        }
+        offset += line.len();
+    }
+
+    entries
+}
+
+/// Keeps only the log entries with matches, so a match shows up with the rest
+/// of its entry, stack trace included
+pub(crate) fn matching_entries(contents: &str, matches: Vec<Match>) -> (String, Vec<Match>) {
+    let entries = entries(contents);
+    let entry_of = |offset: usize| {
+        entries
+            .iter()
+            .position(|entry| offset <= entry.range.end)
+            .unwrap_or(entries.len() - 1)
+    };
+
+    let mut kept = Vec::<Range<usize>>::new();
+    for found in &matches {
+        let first = &entries[entry_of(found.start)];
+        let last = &entries[entry_of(found.end.max(found.start + 1) - 1)];
+        match kept.last_mut() {
+            Some(range) if first.range.start <= range.end => {
+                range.end = range.end.max(last.range.end)
+            }
+            _ => kept.push(first.range.start..last.range.end),
+        }
+    }
+
+    let mut grouped = String::new();
+    let mut shifted = Vec::with_capacity(matches.len());
+    let mut remaining = matches.into_iter().peekable();
+    for range in kept {
+        if !grouped.is_empty() {
+            grouped.push('\n');
+        }
+        let shift = grouped.len() as isize - range.start as isize;
+        grouped.push_str(&contents[range.clone()]);
+        while let Some(found) = remaining.next_if(|found| found.start <= range.end) {
+            let start = (found.start as isize + shift) as usize;
+            let start_line = grouped[..start].matches('\n').count() + 1;
+            shifted.push(Match {
+                start,
+                end: (found.end as isize + shift) as usize,
+                start_line,
+                end_line: start_line + (found.end_line - found.start_line),
+                ..found
+            });
+        }
+    }
+
+    (grouped, shifted)
+}
+
+/// The timestamp a log line starts with, like `2026-10-01T10:00:00.123Z`,
+/// `[2026-10-01 10:00:00,123]` or `2026/10/01 10:00:00`
+fn line_timestamp(line: &str) -> Option<NaiveDateTime> {
+    let line = line.strip_prefix('[').unwrap_or(line);
+    let (date, rest) = date(line)?;
+    let rest = rest.strip_prefix(&['T', ' '][..])?;
+    let (time, rest) = time(rest)?;
+    let (offset, _) = offset(rest);
+
+    Some(date.and_time(time) - offset)
+}
+
+fn parse_bound(bound: &str) -> Result<NaiveDateTime> {
+    let invalid = || {
+        anyhow!(
+            "Invalid time: {}, use a date like 2026-10-01 or 2026-10-01T10:00",
+            bound
+        )
+    };
+    let (date, rest) = date(bound).ok_or_else(invalid)?;
+    if rest.is_empty() {
+        return Ok(date.and_time(NaiveTime::MIN));
+    }
+
+    let rest = rest.strip_prefix(&['T', ' '][..]).ok_or_else(invalid)?;
+    let (time, rest) = time(rest).ok_or_else(invalid)?;
+    match offset(rest) {
+        (offset, "") => Ok(date.and_time(time) - offset),
+        _ => Err(invalid()),
+    }
+}
+
+fn date(text: &str) -> Option<(NaiveDate, &str)> {
+    let (year, rest) = number(text, 4)?;
+    let separator = rest
+        .chars()
+        .next()
+        .filter(|separator| "-/".contains(*separator))?;
+    let (month, rest) = number(&rest[1..], 2)?;
+    let (day, rest) = number(rest.strip_prefix(separator)?, 2)?;
+
+    Some((NaiveDate::from_ymd_opt(year as i32, month, day)?, rest))
+}
+
+/// `HH:MM`, optionally followed by seconds and a fraction of them
+fn time(text: &str) -> Option<(NaiveTime, &str)> {
+    let (hour, rest) = number(text, 2)?;
+    let (minute, mut rest) = number(rest.strip_prefix(':')?, 2)?;
+    let mut second = 0;
+    let mut nanosecond = 0;
+    if let Some((seconds, after)) = rest.strip_prefix(':').and_then(|rest| number(rest, 2)) {
+        second = seconds;
+        rest = after;
+        let fraction = rest
+            .strip_prefix(&['.', ','][..])
+            .filter(|fraction| fraction.starts_with(|character: char| character.is_ascii_digit()));
+        if let Some(fraction) = fraction {
+            let digits = fraction.len()
+                - fraction
+                    .trim_start_matches(|character: char| character.is_ascii_digit())
+                    .len();
+            let kept = digits.min(MAX_FRACTION_DIGITS);
+            nanosecond = fraction[..kept].parse::<u32>().ok()?
+                * 10u32.pow((MAX_FRACTION_DIGITS - kept) as u32);
+            rest = &fraction[digits..];
+        }
+    }
+
+    Some((
+        NaiveTime::from_hms_nano_opt(hour, minute, second, nanosecond)?,
+        rest,
+    ))
+}
+
+/// `Z`, `+01:00` or `-0500`, none counts as UTC
+fn offset(text: &str) -> (Duration, &str) {
+    if let Some(rest) = text.strip_prefix('Z') {
+        return (Duration::zero(), rest);
+    }
+    let sign = match text.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return (Duration::zero(), text),
+    };
+    let parsed = number(&text[1..], 2).and_then(|(hours, rest)| {
+        let rest = rest.strip_prefix(':').unwrap_or(rest);
+        let (minutes, rest) = number(rest, 2)?;
+        Some((hours * 60 + minutes, rest))
+    });
+
+    match parsed {
+        Some((minutes, rest)) => (Duration::minutes(sign * minutes as i64), rest),
+        None => (Duration::zero(), text),
+    }
+}
+
+/// A number written with exactly `digits` ASCII digits at the start of the text
+fn number(text: &str, digits: usize) -> Option<(u32, &str)> {
+    let written = text.get(..digits)?;
+    if !written.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    Some((written.parse().ok()?, &text[digits..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "starting up
+2026-10-01T09:59:58Z INFO listening on :8080
+2026-10-01T10:00:01.250Z ERROR request failed
+java.lang.NullPointerException: user
+    at com.example.Users.find(Users.java:42)
+2026-10-01T11:30:00+01:00 WARN slow request
+2026-10-01T12:00:00Z INFO shutting down
+";
+
+    fn time(text: &str) -> NaiveDateTime {
+        parse_bound(text).unwrap()
+    }
+
+    #[test]
+    fn it_detects_common_timestamp_formats() {
+        let expected = time("2026-10-01T10:00:00");
+
+        for line in [
+            "2026-10-01T10:00:00Z INFO",
+            "2026-10-01 11:00:00+01:00 INFO",
+            "[2026-10-01 10:00:00,000] INFO",
+            "2026/10/01 10:00 INFO",
+            "2026-10-01T05:00:00-0500 INFO",
+        ]
+        .iter()
+        .copied()
+        {
+            assert_eq!(line_timestamp(line), Some(expected), "{}", line);
+        }
+        assert_eq!(line_timestamp("    at Users.find(Users.java:42)"), None);
+        assert_eq!(line_timestamp("2026-13-01 10:00:00 INFO"), None);
+        assert!(TimeRange::parse(Some("yesterday"), None).is_err());
+        assert!(TimeRange::parse(None, Some("2026-10-01T10:00junk")).is_err());
+    }
+
+    #[test]
+    fn it_keeps_the_entries_within_the_range_with_their_stack_traces() -> Result<()> {
+        let range = TimeRange::parse(Some("2026-10-01T10:00"), Some("2026-10-01T12:00"))?;
+
+        assert_eq!(
+            range.filter(LOG).unwrap(),
+            "2026-10-01T10:00:01.250Z ERROR request failed
+java.lang.NullPointerException: user
+    at com.example.Users.find(Users.java:42)
+2026-10-01T11:30:00+01:00 WARN slow request"
+        );
+        assert_eq!(range.filter("no timestamps here"), None);
+        assert_eq!(
+            TimeRange::parse(Some("2027-01-01"), None)?.filter(LOG),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_groups_matches_with_their_log_entry() {
+        let start = LOG.find("Users.java").unwrap();
+        let found = Match {
+            pattern: 0,
+            start,
+            end: start + "Users.java".len(),
+            start_line: 5,
+            end_line: 5,
+        };
+
+        let (contents, matches) = matching_entries(LOG, vec![found]);
+
+        assert_eq!(
+            contents,
+            "2026-10-01T10:00:01.250Z ERROR request failed
+java.lang.NullPointerException: user
+    at com.example.Users.find(Users.java:42)"
+        );
+        assert_eq!(&contents[matches[0].start..matches[0].end], "Users.java");
+        assert_eq!(matches[0].start_line, 3);
+    }
+}
@@ -0,0 +1,578 @@
+use std::{fs, path::Path};
+
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use anyhow::Result;
+use config::Language;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    analyzer::Analyzer,
+    normalization::{fold, Folded},
+    proximity::Proximity,
+    tokenizer::tokenize,
+    SearchOptions,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum MatchMode {
+    /// Files containing any of the patterns
+    #[default]
+    Matching,
+    /// Files that don't contain any of the patterns
+    FilesWithoutMatch,
+    /// Lines not containing any of the patterns, for every file that has any
+    Inverted,
+}
+
+impl MatchMode {
+    pub(crate) fn apply(
+        &self,
+        matcher: &Matcher,
+        file_contents: String,
+    ) -> Option<(String, Vec<Match>)> {
+        match self {
+            MatchMode::Matching => {
+                let matches = matcher.find(&file_contents);
+                Some((file_contents, matches)).filter(|(_, matches)| !matches.is_empty())
+            }
+            MatchMode::FilesWithoutMatch => Some((file_contents, Vec::new()))
+                .filter(|(contents, _)| !matcher.is_match(contents)),
+            MatchMode::Inverted => {
+                let lines = file_contents
+                    .lines()
+                    .filter(|line| !matcher.is_match(line))
+                    .collect::<Vec<&str>>();
+                Some((lines.join("\n"), Vec::new())).filter(|_| !lines.is_empty())
+            }
+        }
+    }
+}
+
+/// A single hit, tagged with the index of the pattern that produced it.
+/// Lines are 1-based and inclusive, so a multiline hit spans `start_line..=end_line`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Match {
+    pub pattern: usize,
+    pub start: usize,
+    pub end: usize,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+#[derive(Debug)]
+enum PatternSet {
+    Literal(Box<AhoCorasick>),
+    /// Patterns are regular expressions that may span line breaks, each one
+    /// wrapped in its own capture group to know which of them matched. Patterns
+    /// can have groups of their own, so the slot of each wrapping group is kept.
+    Multiline(Regex, Vec<usize>),
+}
+
+impl PatternSet {
+    fn new(patterns: &[&String], multiline: bool) -> Result<Self> {
+        if !multiline {
+            let automaton = AhoCorasickBuilder::new()
+                .match_kind(MatchKind::LeftmostLongest)
+                .build(patterns);
+            return Ok(PatternSet::Literal(Box::new(automaton)));
+        }
+
+        let mut groups = Vec::new();
+        let mut group = 1;
+        for pattern in patterns {
+            groups.push(group);
+            group += Regex::new(pattern)?.captures_len();
+        }
+        let alternation = patterns
+            .iter()
+            .map(|pattern| format!("({})", pattern))
+            .collect::<Vec<String>>()
+            .join("|");
+        let regex = RegexBuilder::new(&alternation)
+            .multi_line(true)
+            .dot_matches_new_line(true)
+            .build()?;
+
+        Ok(PatternSet::Multiline(regex, groups))
+    }
+
+    fn is_match(&self, haystack: &str) -> bool {
+        match self {
+            PatternSet::Literal(automaton) => automaton.is_match(haystack),
+            PatternSet::Multiline(regex, _) => regex.is_match(haystack),
+        }
+    }
+
+    fn find(&self, haystack: &str) -> Vec<(usize, usize, usize)> {
+        match self {
+            PatternSet::Literal(automaton) => automaton
+                .find_iter(haystack)
+                .map(|found| (found.pattern(), found.start(), found.end()))
+                .collect(),
+            PatternSet::Multiline(regex, groups) => regex
+                .captures_iter(haystack)
+                .filter_map(|captures| {
+                    let whole = captures.get(0)?;
+                    let pattern = groups
+                        .iter()
+                        .position(|group| captures.get(*group).is_some())?;
+                    Some((pattern, whole.start(), whole.end()))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Finds every plain pattern in a single pass over the file contents, while
+/// phrase and proximity queries are checked against the file's tokens. Sources
+/// with a language analyzer match the stems of the patterns instead of their text.
+#[derive(Debug)]
+pub(crate) struct Matcher {
+    /// Plain patterns, along with the index each one has in the search options
+    patterns: Option<(PatternSet, Vec<usize>)>,
+    proximities: Vec<(usize, Proximity)>,
+    /// Stems of every pattern that has any once stop words are dropped
+    analyzed: Vec<(usize, Vec<String>)>,
+    analyzer: Option<Analyzer>,
+    ignore_diacritics: bool,
+}
+
+impl Matcher {
+    pub(crate) fn new(options: &SearchOptions, language: Option<Language>) -> Result<Self> {
+        let analyzer = language.map(Analyzer::new);
+        let patterns = match options.ignore_diacritics {
+            true => options
+                .patterns
+                .iter()
+                .map(|pattern| fold(pattern))
+                .collect(),
+            false => options.patterns.clone(),
+        };
+        let mut plain_patterns = Vec::new();
+        let mut plain_indexes = Vec::new();
+        let mut proximities = Vec::new();
+        let mut analyzed = Vec::new();
+        for (index, pattern) in patterns.iter().enumerate() {
+            let terms = match &analyzer {
+                Some(analyzer) if !options.multiline && is_plain_words(pattern) => {
+                    analyzer.terms(pattern)
+                }
+                _ => Vec::new(),
+            };
+            // Multiline patterns are regular expressions, so quotes and `~` keep their meaning
+            let proximity = match options.multiline {
+                true => None,
+                false => Proximity::parse(pattern),
+            };
+            match proximity {
+                Some(proximity) => proximities.push((index, proximity)),
+                None if !terms.is_empty() => analyzed.push((index, terms)),
+                None => {
+                    plain_patterns.push(pattern);
+                    plain_indexes.push(index);
+                }
+            }
+        }
+
+        let patterns = match plain_patterns.is_empty() {
+            true => None,
+            false => Some((
+                PatternSet::new(&plain_patterns, options.multiline)?,
+                plain_indexes,
+            )),
+        };
+
+        Ok(Self {
+            patterns,
+            proximities,
+            analyzed,
+            analyzer,
+            ignore_diacritics: options.ignore_diacritics,
+        })
+    }
+
+    pub(crate) fn is_match(&self, haystack: &str) -> bool {
+        if self.ignore_diacritics {
+            return self.is_match_exactly(&fold(haystack));
+        }
+
+        self.is_match_exactly(haystack)
+    }
+
+    fn is_match_exactly(&self, haystack: &str) -> bool {
+        let matches_patterns = match &self.patterns {
+            Some((pattern_set, _)) => pattern_set.is_match(haystack),
+            None => false,
+        };
+
+        matches_patterns
+            || !self.find_proximities(haystack).is_empty()
+            || !self.find_analyzed(haystack).is_empty()
+    }
+
+    pub(crate) fn find(&self, haystack: &str) -> Vec<Match> {
+        let mut found = match self.ignore_diacritics {
+            true => {
+                let folded = Folded::new(haystack);
+                self.find_exactly(&folded.text)
+                    .into_iter()
+                    .map(|(pattern, start, end)| {
+                        let start = folded.original_offset(start);
+                        (pattern, start, folded.original_offset(end))
+                    })
+                    .collect()
+            }
+            false => self.find_exactly(haystack),
+        };
+        found.sort_by_key(|(_, start, _)| *start);
+
+        let mut line = 1;
+        let mut line_counted_until = 0;
+        found
+            .into_iter()
+            .map(|(pattern, start, end)| {
+                line += haystack[line_counted_until..start].matches('\n').count();
+                line_counted_until = start;
+                Match {
+                    pattern,
+                    start,
+                    end,
+                    start_line: line,
+                    end_line: line + haystack[start..end].matches('\n').count(),
+                }
+            })
+            .collect()
+    }
+
+    /// Matches as `(pattern, start, end)`, unsorted
+    fn find_exactly(&self, haystack: &str) -> Vec<(usize, usize, usize)> {
+        let mut found = match &self.patterns {
+            Some((pattern_set, indexes)) => pattern_set
+                .find(haystack)
+                .into_iter()
+                .map(|(pattern, start, end)| (indexes[pattern], start, end))
+                .collect(),
+            None => Vec::new(),
+        };
+        found.extend(self.find_proximities(haystack));
+        found.extend(self.find_analyzed(haystack));
+        found
+    }
+
+    fn find_proximities(&self, haystack: &str) -> Vec<(usize, usize, usize)> {
+        if self.proximities.is_empty() {
+            return Vec::new();
+        }
+
+        let tokens = tokenize(haystack);
+        self.proximities
+            .iter()
+            .flat_map(|(pattern, proximity)| {
+                proximity
+                    .find(&tokens)
+                    .into_iter()
+                    .map(move |(start, end)| (*pattern, start, end))
+            })
+            .collect()
+    }
+
+    /// Every word sharing a stem with a pattern, as long as all of the pattern's stems are found
+    fn find_analyzed(&self, haystack: &str) -> Vec<(usize, usize, usize)> {
+        let analyzer = match &self.analyzer {
+            Some(analyzer) if !self.analyzed.is_empty() => analyzer,
+            _ => return Vec::new(),
+        };
+
+        let tokens = analyzer.analyze(haystack);
+        self.analyzed
+            .iter()
+            .flat_map(|(pattern, terms)| {
+                let found = tokens
+                    .iter()
+                    .filter(|token| terms.contains(&token.term))
+                    .map(|token| (*pattern, token.start, token.end))
+                    .collect::<Vec<(usize, usize, usize)>>();
+                let has_every_term = terms
+                    .iter()
+                    .all(|term| tokens.iter().any(|token| &token.term == term));
+                match has_every_term {
+                    true => found,
+                    false => Vec::new(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Only patterns made of words go through the analyzer, so code such as `foo.bar()` stays exact
+fn is_plain_words(pattern: &str) -> bool {
+    pattern
+        .chars()
+        .all(|character| character.is_alphanumeric() || character.is_whitespace())
+}
+
+/// Reads one pattern per line, ignoring blank lines
+pub fn read_patterns<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+    let patterns = fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(String::from)
+        .collect();
+
+    Ok(patterns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn matcher(patterns: &[&str], multiline: bool) -> Matcher {
+        Matcher::new(
+            &SearchOptions {
+                patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+                multiline,
+                ..SearchOptions::default()
+            },
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn it_tags_each_match_with_its_pattern_and_line() {
+        let matcher = matcher(&["old_api", "legacy_call"], false);
+
+        let matches = matcher.find("fn a() {\n    legacy_call();\n    old_api();\n}");
+
+        assert_eq!(
+            matches,
+            vec![
+                Match {
+                    pattern: 1,
+                    start: 13,
+                    end: 24,
+                    start_line: 2,
+                    end_line: 2
+                },
+                Match {
+                    pattern: 0,
+                    start: 32,
+                    end: 39,
+                    start_line: 3,
+                    end_line: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_matches_patterns_spanning_multiple_lines() {
+        let matcher = matcher(&[r"#\[test\]\s*fn \w+"], true);
+
+        let matches = matcher.find("mod tests {\n    #[test]\n    fn it_works() {}\n}");
+
+        assert_eq!(
+            matches,
+            vec![Match {
+                pattern: 0,
+                start: 16,
+                end: 39,
+                start_line: 2,
+                end_line: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn it_tags_multiline_matches_with_their_pattern() {
+        let matcher = matcher(&["first", "second.*third"], true);
+
+        let matches = matcher.find("second\nthird");
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pattern, 1);
+        assert_eq!((matches[0].start_line, matches[0].end_line), (1, 2));
+    }
+
+    #[test]
+    fn it_tags_multiline_matches_of_patterns_with_their_own_groups() {
+        let matcher = matcher(&["(a)(b)", "c(d)", "e"], true);
+
+        let matches = matcher.find("ab cd e");
+
+        let found = matches
+            .iter()
+            .map(|found| found.pattern)
+            .collect::<Vec<usize>>();
+        assert_eq!(found, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn it_tags_multiline_matches_of_patterns_with_named_groups() {
+        let matcher = matcher(&["(?P<p1>x)y", "(?P<key>\\w+)=1", "z"], true);
+
+        let matches = matcher.find("z key=1 xy");
+
+        assert_eq!(
+            matches
+                .iter()
+                .map(|found| found.pattern)
+                .collect::<Vec<usize>>(),
+            vec![2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn it_reads_quotes_and_tildes_as_regex_when_multiline() {
+        let matcher = matcher(&["\"deploy service\"~2"], true);
+
+        assert!(matcher.is_match("\"deploy service\"~2"));
+        assert!(!matcher.is_match("deploy the service"));
+    }
+
+    #[test]
+    fn it_prefers_the_longest_pattern() {
+        let matcher = matcher(&["config", "configuration"], false);
+
+        let matches = matcher.find("configuration");
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pattern, 1);
+    }
+
+    #[test]
+    fn it_knows_which_regex_matched_even_with_groups_inside_the_patterns() {
+        let matcher = matcher(&["(a|b)c", "(d)(e)"], true);
+
+        let matches = matcher.find("de ac");
+
+        assert_eq!(
+            matches
+                .iter()
+                .map(|found| found.pattern)
+                .collect::<Vec<usize>>(),
+            vec![1, 0]
+        );
+    }
+
+    #[test]
+    fn it_mixes_proximity_queries_with_plain_patterns() {
+        let matcher = matcher(&["rollback", "\"deploy service\"~2"], false);
+
+        let matches = matcher.find("Deploy the new service\nthen rollback");
+
+        assert_eq!(
+            matches,
+            vec![
+                Match {
+                    pattern: 1,
+                    start: 0,
+                    end: 22,
+                    start_line: 1,
+                    end_line: 1
+                },
+                Match {
+                    pattern: 0,
+                    start: 28,
+                    end: 36,
+                    start_line: 2,
+                    end_line: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_ignores_diacritics_and_maps_matches_to_the_original_text() {
+        let matcher = Matcher::new(
+            &SearchOptions {
+                patterns: vec!["configuração".to_string()],
+                ignore_diacritics: true,
+                ..SearchOptions::default()
+            },
+            None,
+        )
+        .unwrap();
+        let haystack = "# Configuracao\nconfigurac\u{327}a\u{303}o = 1";
+
+        let matches = matcher.find(haystack);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(
+            &haystack[matches[0].start..matches[0].end],
+            "configurac\u{327}a\u{303}o"
+        );
+        assert_eq!(matches[0].start_line, 2);
+        assert!(matcher.is_match("configuracao"));
+    }
+
+    #[test]
+    fn it_matches_every_form_of_the_words_when_using_an_analyzer() {
+        let options = SearchOptions {
+            patterns: vec!["deploying services".to_string(), "the".to_string()],
+            ..SearchOptions::default()
+        };
+        let matcher = Matcher::new(&options, Some(Language::English)).unwrap();
+
+        let matches = matcher.find("We deployed the service.\nDeploy often");
+
+        assert_eq!(
+            matches
+                .iter()
+                .map(|found| (found.pattern, found.start_line))
+                .collect::<Vec<(usize, usize)>>(),
+            vec![(0, 1), (1, 1), (0, 1), (0, 2)]
+        );
+        assert!(!matcher.is_match("deployed without anything else"));
+    }
+
+    #[test]
+    fn it_keeps_code_and_regex_patterns_exact_when_using_an_analyzer() {
+        let options = SearchOptions {
+            patterns: vec!["services.start()".to_string()],
+            ..SearchOptions::default()
+        };
+        let matcher = Matcher::new(&options, Some(Language::English)).unwrap();
+
+        assert!(matcher.is_match("services.start();"));
+        assert!(!matcher.is_match("the service started"));
+
+        let options = SearchOptions {
+            patterns: vec!["deploying".to_string()],
+            multiline: true,
+            ..SearchOptions::default()
+        };
+        let matcher = Matcher::new(&options, Some(Language::English)).unwrap();
+
+        assert!(!matcher.is_match("deployed"));
+    }
+
+    #[test]
+    fn it_keeps_only_the_lines_without_any_pattern_when_inverted() {
+        let matcher = matcher(&["timeout", "retries"], false);
+        let file_contents = "timeout = 30\nretries = 3\nname = \"api\"".to_string();
+
+        let result = MatchMode::Inverted.apply(&matcher, file_contents);
+
+        assert_eq!(result, Some(("name = \"api\"".to_string(), Vec::new())));
+        assert_eq!(
+            MatchMode::Inverted.apply(&matcher, "timeout = 1".to_string()),
+            None
+        );
+    }
+
+    #[test]
+    fn it_reads_the_patterns_from_a_file() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        writeln!(file, "old_api\n\n  legacy_call  ")?;
+
+        let patterns = read_patterns(file.path())?;
+
+        assert_eq!(patterns, vec!["old_api", "legacy_call"]);
+        Ok(())
+    }
+}
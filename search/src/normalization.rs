@@ -0,0 +1,68 @@
+use unicode_normalization::char::{decompose_canonical, is_combining_mark};
+
+/// Text with its diacritics removed, so "configuração" and "configuracao" read
+/// the same, whether the original was composed (NFC) or decomposed (NFD).
+/// Keeps track of where each byte came from to map matches back to the original.
+#[derive(Debug)]
+pub(crate) struct Folded {
+    pub(crate) text: String,
+    original_offsets: Vec<usize>,
+}
+
+impl Folded {
+    pub(crate) fn new(original: &str) -> Self {
+        let mut text = String::with_capacity(original.len());
+        let mut original_offsets = Vec::with_capacity(original.len() + 1);
+
+        for (offset, character) in original.char_indices() {
+            decompose_canonical(character, |decomposed| {
+                if !is_combining_mark(decomposed) {
+                    text.push(decomposed);
+                    original_offsets.resize(original_offsets.len() + decomposed.len_utf8(), offset);
+                }
+            });
+        }
+        original_offsets.push(original.len());
+
+        Self {
+            text,
+            original_offsets,
+        }
+    }
+
+    /// Where a match boundary in the folded text falls in the original one. An end
+    /// boundary lands after any combining marks of the previous character.
+    pub(crate) fn original_offset(&self, folded_offset: usize) -> usize {
+        self.original_offsets[folded_offset]
+    }
+}
+
+pub(crate) fn fold(text: &str) -> String {
+    Folded::new(text).text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPOSED: &str = "configura\u{e7}\u{e3}o";
+    const DECOMPOSED: &str = "configurac\u{327}a\u{303}o";
+
+    #[test]
+    fn it_removes_diacritics_from_composed_and_decomposed_text() {
+        assert_eq!(fold(COMPOSED), "configuracao");
+        assert_eq!(fold(DECOMPOSED), "configuracao");
+    }
+
+    #[test]
+    fn it_maps_offsets_back_to_the_original_text() {
+        let original = format!("a {}!", DECOMPOSED);
+        let folded = Folded::new(&original);
+        let start = folded.text.find("configuracao").unwrap();
+        let end = start + "configuracao".len();
+
+        let original_match = &original[folded.original_offset(start)..folded.original_offset(end)];
+
+        assert_eq!(original_match, DECOMPOSED);
+    }
+}
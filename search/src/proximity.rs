@@ -0,0 +1,133 @@
+use crate::tokenizer::{tokenize, Token};
+
+/// A `"term1 term2"` phrase matches the words next to each other and in order,
+/// while `"term1 term2"~N` matches them in any order with at most N other words
+/// in between. Words are compared case-insensitively, as produced by the tokenizer.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Proximity {
+    words: Vec<String>,
+    slop: Option<usize>,
+}
+
+impl Proximity {
+    /// Quoted single words without a slop are left alone so searching for
+    /// `"name"` still finds the quotes, as it always has
+    pub(crate) fn parse(pattern: &str) -> Option<Self> {
+        let (phrase, slop) = pattern.strip_prefix('"')?.rsplit_once('"')?;
+        let slop = match slop {
+            "" => None,
+            slop => Some(slop.strip_prefix('~')?.parse().ok()?),
+        };
+        let words = tokenize(phrase)
+            .into_iter()
+            .map(|token| token.term)
+            .collect::<Vec<String>>();
+
+        if words.is_empty() || (words.len() < 2 && slop.is_none()) {
+            return None;
+        }
+
+        Some(Self { words, slop })
+    }
+
+    /// Byte ranges going from the first to the last word of every occurrence
+    pub(crate) fn find(&self, tokens: &[Token]) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+        let mut first = 0;
+
+        while first < tokens.len() {
+            match self.occurrence_at(tokens, first) {
+                Some(last) => {
+                    found.push((tokens[first].start, tokens[last].end));
+                    first = last + 1;
+                }
+                None => first += 1,
+            }
+        }
+
+        found
+    }
+
+    /// Index of the last token of an occurrence starting at `first`, if there's one
+    fn occurrence_at(&self, tokens: &[Token], first: usize) -> Option<usize> {
+        let slop = match self.slop {
+            Some(slop) => slop,
+            None => {
+                let window = tokens.get(first..first + self.words.len())?;
+                let in_order = window
+                    .iter()
+                    .zip(self.words.iter())
+                    .all(|(token, word)| &token.term == word);
+                return Some(first + self.words.len() - 1).filter(|_| in_order);
+            }
+        };
+
+        let mut missing = self.words.iter().collect::<Vec<&String>>();
+        let window_end = tokens.len().min(first + self.words.len() + slop);
+        for (offset, token) in tokens[first..window_end].iter().enumerate() {
+            match missing.iter().position(|word| **word == token.term) {
+                Some(index) => {
+                    missing.swap_remove(index);
+                    if missing.is_empty() {
+                        return Some(first + offset);
+                    }
+                }
+                None if offset == 0 => return None,
+                None => {}
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(query: &str, text: &str) -> Vec<(usize, usize)> {
+        Proximity::parse(query).unwrap().find(&tokenize(text))
+    }
+
+    #[test]
+    fn it_parses_phrases_and_proximity_queries() {
+        assert_eq!(
+            Proximity::parse("\"Deploy Service\"~5"),
+            Some(Proximity {
+                words: vec!["deploy".to_string(), "service".to_string()],
+                slop: Some(5)
+            })
+        );
+        assert_eq!(
+            Proximity::parse("\"deploy service\""),
+            Some(Proximity {
+                words: vec!["deploy".to_string(), "service".to_string()],
+                slop: None
+            })
+        );
+        assert_eq!(Proximity::parse("\"name\""), None);
+        assert_eq!(Proximity::parse("deploy service"), None);
+        assert_eq!(Proximity::parse("\"deploy service\"~x"), None);
+    }
+
+    #[test]
+    fn it_matches_phrases_only_in_order() {
+        assert_eq!(
+            find("\"deploy the service\"", "Deploy the service."),
+            vec![(0, 18)]
+        );
+        assert_eq!(find("\"deploy the service\"", "service the deploy"), vec![]);
+    }
+
+    #[test]
+    fn it_matches_words_within_the_given_distance_in_any_order() {
+        let text = "the service we deploy daily";
+
+        assert_eq!(find("\"deploy service\"~1", text), vec![(4, 21)]);
+        assert_eq!(find("\"deploy service\"~0", text), vec![]);
+        assert_eq!(
+            find("\"deploy service\"~0", "service deploy"),
+            vec![(0, 14)]
+        );
+    }
+}
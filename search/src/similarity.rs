@@ -0,0 +1,237 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use config::Config;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    diagnostics::{Diagnostic, Diagnostics},
+    tokenizer::tokenize,
+    walker::Walker,
+    SearchResult, SearchResults,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimilarFile {
+    pub source: PathBuf,
+    pub path: PathBuf,
+    /// Cosine similarity of the TF-IDF weighted terms, from 0 to 1
+    pub score: f64,
+    #[serde(skip)]
+    pub contents: String,
+}
+
+impl From<SimilarFile> for SearchResult {
+    fn from(similar_file: SimilarFile) -> Self {
+        Self {
+            source: similar_file.source,
+            path: similar_file.path,
+            contents: similar_file.contents,
+            bytes: None,
+            matches: Vec::new(),
+        }
+    }
+}
+
+/// Files most similar to a given one, along with the ones that couldn't be read
+#[derive(Debug, Serialize)]
+pub struct SimilarFiles {
+    pub files: Vec<SimilarFile>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl From<SimilarFiles> for SearchResults {
+    fn from(similar_files: SimilarFiles) -> Self {
+        Self {
+            results: similar_files
+                .files
+                .into_iter()
+                .map(SearchResult::from)
+                .collect(),
+            diagnostics: similar_files.diagnostics,
+            ..Self::default()
+        }
+    }
+}
+
+/// How many times each term shows up in a file
+type TermCounts = HashMap<String, usize>;
+
+/// Finds the files that look the most like a given one. Terms are weighted with
+/// TF-IDF so words shared by every file, like `the` or `fn`, count for little.
+pub struct Similarity;
+
+impl Similarity {
+    pub fn find(config: &Config, path: &Path, limit: usize) -> Result<SimilarFiles> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| anyhow!("Unable to read file: {:?}, due to {}", path, error))?;
+        let target_path = path.canonicalize()?;
+        let target = Self::term_counts(&contents);
+
+        let diagnostics = Diagnostics::default();
+        let documents = config
+            .config_map
+            .sources
+            .par_iter()
+            .flat_map(|source_path| {
+                Walker::source_files(source_path, None, &diagnostics)
+                    .flatten()
+                    .map(move |source_file| {
+                        let term_counts = Self::term_counts(&source_file.contents);
+                        (source_path, source_file.path, term_counts)
+                    })
+            })
+            .filter(|(_, path, _)| path.canonicalize().ok().as_ref() != Some(&target_path))
+            .collect::<Vec<(&PathBuf, PathBuf, TermCounts)>>();
+
+        let mut document_frequencies = HashMap::<&str, usize>::new();
+        for term_counts in documents
+            .iter()
+            .map(|(_, _, term_counts)| term_counts)
+            .chain(std::iter::once(&target))
+        {
+            for term in term_counts.keys() {
+                *document_frequencies.entry(term).or_default() += 1;
+            }
+        }
+        let idf = |term: &str| {
+            let documents_count = (documents.len() + 1) as f64;
+            let frequency = document_frequencies.get(term).copied().unwrap_or_default() as f64;
+            ((1.0 + documents_count) / (1.0 + frequency)).ln() + 1.0
+        };
+        let target_weights = Self::weights(&target, &idf);
+
+        let mut similar_files = documents
+            .par_iter()
+            .map(|(source_path, path, term_counts)| {
+                let weights = Self::weights(term_counts, &idf);
+                (*source_path, path, Self::cosine(&target_weights, &weights))
+            })
+            .filter(|(_, _, score)| *score > 0.0)
+            .collect::<Vec<(&PathBuf, &PathBuf, f64)>>();
+        similar_files.sort_by(|(_, path_a, score_a), (_, path_b, score_b)| {
+            score_b
+                .partial_cmp(score_a)
+                .unwrap_or(Ordering::Equal)
+                .then(path_a.cmp(path_b))
+        });
+        similar_files.truncate(limit);
+
+        let files = similar_files
+            .into_iter()
+            .filter_map(|(source_path, path, score)| {
+                Some(SimilarFile {
+                    source: source_path.clone(),
+                    path: path.clone(),
+                    score,
+                    contents: fs::read_to_string(path).ok()?,
+                })
+            })
+            .collect();
+
+        Ok(SimilarFiles {
+            files,
+            diagnostics: diagnostics.into_sorted(),
+        })
+    }
+
+    fn term_counts(contents: &str) -> TermCounts {
+        let mut term_counts = TermCounts::new();
+        for token in tokenize(contents) {
+            *term_counts.entry(token.term).or_default() += 1;
+        }
+        term_counts
+    }
+
+    fn weights<'terms>(
+        term_counts: &'terms TermCounts,
+        idf: &impl Fn(&str) -> f64,
+    ) -> HashMap<&'terms str, f64> {
+        term_counts
+            .iter()
+            .map(|(term, count)| (term.as_str(), *count as f64 * idf(term)))
+            .collect()
+    }
+
+    fn cosine(weights_a: &HashMap<&str, f64>, weights_b: &HashMap<&str, f64>) -> f64 {
+        let norm = |weights: &HashMap<&str, f64>| {
+            weights
+                .values()
+                .map(|weight| weight * weight)
+                .sum::<f64>()
+                .sqrt()
+        };
+        let norms = norm(weights_a) * norm(weights_b);
+        if norms == 0.0 {
+            return 0.0;
+        }
+
+        let dot_product = weights_a
+            .iter()
+            .filter_map(|(term, weight)| Some(weight * weights_b.get(term)?))
+            .sum::<f64>();
+
+        dot_product / norms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::Configuration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_ranks_the_most_similar_files_first() -> Result<()> {
+        let source = tempdir()?;
+        let target = source.path().join("nginx.conf");
+        fs::write(
+            &target,
+            "server listen 80 server_name example.com root /var/www",
+        )?;
+        fs::write(
+            source.path().join("copy.conf"),
+            "server listen 8080 server_name example.com root /var/www",
+        )?;
+        fs::write(
+            source.path().join("other.conf"),
+            "server listen 443 ssl_certificate cert.pem",
+        )?;
+        fs::write(source.path().join("unrelated.md"), "Meeting notes")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+
+        let similar_files = Similarity::find(&config, &target, 10)?.files;
+
+        let paths = similar_files
+            .iter()
+            .map(|similar_file| similar_file.path.file_name().unwrap().to_str().unwrap())
+            .collect::<Vec<&str>>();
+        assert_eq!(paths, vec!["copy.conf", "other.conf"]);
+        assert!(similar_files[0].score > similar_files[1].score);
+        assert_eq!(
+            similar_files[0].contents,
+            "server listen 8080 server_name example.com root /var/www"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_scores_identical_weights_as_one() {
+        let weights = vec![("deploy", 2.0), ("service", 1.0)]
+            .into_iter()
+            .collect::<HashMap<&str, f64>>();
+
+        let score = Similarity::cosine(&weights, &weights);
+
+        assert!((score - 1.0).abs() < 1e-9);
+        assert_eq!(Similarity::cosine(&weights, &HashMap::new()), 0.0);
+    }
+}
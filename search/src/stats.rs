@@ -0,0 +1,179 @@
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{walker::SourceFile, Limit, SearchResult};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GroupStats {
+    pub files: usize,
+    pub matches: usize,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SearchStats {
+    pub files_scanned: usize,
+    pub bytes_read: u64,
+    pub files_skipped: usize,
+    #[serde(serialize_with = "as_seconds", deserialize_with = "from_seconds")]
+    pub elapsed: Duration,
+    pub by_source: BTreeMap<PathBuf, GroupStats>,
+    pub by_directory: BTreeMap<PathBuf, GroupStats>,
+    pub by_extension: BTreeMap<String, GroupStats>,
+    /// Limits that cut the search short
+    pub limits_hit: Vec<Limit>,
+}
+
+impl SearchStats {
+    pub(crate) fn new(
+        results: &[SearchResult],
+        counters: Counters,
+        elapsed: Duration,
+        limits_hit: Vec<Limit>,
+    ) -> Self {
+        let mut stats = Self {
+            files_scanned: counters.files_scanned.into_inner(),
+            bytes_read: counters.bytes_read.into_inner(),
+            files_skipped: counters.files_skipped.into_inner(),
+            elapsed,
+            limits_hit,
+            ..Self::default()
+        };
+
+        for result in results {
+            let directory = result.path.parent().unwrap_or(&result.source).to_path_buf();
+            let extension = match result.path.extension() {
+                Some(extension) => extension.to_string_lossy().into_owned(),
+                None => String::from("(none)"),
+            };
+
+            for group in [
+                stats.by_source.entry(result.source.clone()).or_default(),
+                stats.by_directory.entry(directory).or_default(),
+                stats.by_extension.entry(extension).or_default(),
+            ] {
+                group.files += 1;
+                group.matches += result.matches.len();
+            }
+        }
+
+        stats
+    }
+}
+
+/// Tallies every file read while the search runs across the thread pool
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    files_scanned: AtomicUsize,
+    bytes_read: AtomicU64,
+    files_skipped: AtomicUsize,
+}
+
+impl Counters {
+    pub(crate) fn count<C: AsRef<[u8]>>(
+        &self,
+        source_file: Option<SourceFile<C>>,
+    ) -> Option<SourceFile<C>> {
+        match &source_file {
+            Some(file) => {
+                self.files_scanned.fetch_add(1, Ordering::Relaxed);
+                self.bytes_read
+                    .fetch_add(file.contents.as_ref().len() as u64, Ordering::Relaxed);
+            }
+            None => {
+                self.files_skipped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        source_file
+    }
+}
+
+fn as_seconds<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+fn from_seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    f64::deserialize(deserializer).map(Duration::from_secs_f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Match;
+
+    fn search_result(path: &str, matches: usize) -> SearchResult {
+        let found = Match {
+            pattern: 0,
+            start: 0,
+            end: 0,
+            start_line: 1,
+            end_line: 1,
+        };
+        SearchResult {
+            source: PathBuf::from("source"),
+            path: PathBuf::from("source").join(path),
+            contents: String::new(),
+            bytes: None,
+            matches: vec![found; matches],
+        }
+    }
+
+    #[test]
+    fn it_groups_matches_by_source_directory_and_extension() {
+        let results = [
+            search_result("a/d.txt", 2),
+            search_result("a/e.rs", 1),
+            search_result("b/Makefile", 3),
+        ];
+
+        let stats = SearchStats::new(
+            &results,
+            Counters::default(),
+            Duration::default(),
+            Vec::new(),
+        );
+
+        assert_eq!(
+            stats.by_source[&PathBuf::from("source")],
+            GroupStats {
+                files: 3,
+                matches: 6
+            }
+        );
+        assert_eq!(
+            stats.by_directory[&PathBuf::from("source/a")],
+            GroupStats {
+                files: 2,
+                matches: 3
+            }
+        );
+        assert_eq!(
+            stats.by_extension["(none)"],
+            GroupStats {
+                files: 1,
+                matches: 3
+            }
+        );
+    }
+
+    #[test]
+    fn it_counts_scanned_and_skipped_files() {
+        let counters = Counters::default();
+
+        counters.count(Some(SourceFile {
+            path: PathBuf::from("file.txt"),
+            contents: String::from("12345"),
+        }));
+        counters.count::<String>(None);
+        let stats = SearchStats::new(&[], counters, Duration::default(), Vec::new());
+
+        assert_eq!(stats.files_scanned, 1);
+        assert_eq!(stats.bytes_read, 5);
+        assert_eq!(stats.files_skipped, 1);
+    }
+}
@@ -0,0 +1,400 @@
+use std::{fmt::Write, path::Path};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+use crate::Match;
+
+const MAX_RENDERED_VALUE_LENGTH: usize = 80;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    /// `*` or `[*]`, any key or index
+    Any,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step<'document> {
+    Key(&'document str),
+    Index(usize),
+}
+
+/// A path into a document like `.dependencies.serde`, `.servers[0].port` or
+/// `.services.*.image`. Keys that aren't plain words can be quoted, `."a.b"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathExpr {
+    segments: Vec<Segment>,
+}
+
+impl PathExpr {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let invalid = |reason: &str| anyhow!("Invalid path expression: {}, {}", expression, reason);
+        let mut segments = Vec::new();
+        let mut rest = expression;
+
+        while !rest.is_empty() {
+            if let Some(index) = rest.strip_prefix('[') {
+                let (index, after) = index.split_once(']').ok_or_else(|| invalid("missing ]"))?;
+                segments.push(match index {
+                    "*" => Segment::Any,
+                    index => Segment::Index(index.parse().map_err(|_| invalid("bad index"))?),
+                });
+                rest = after;
+            } else if let Some(key) = rest.strip_prefix(".\"") {
+                let (key, after) = key.split_once('"').ok_or_else(|| invalid("missing \""))?;
+                segments.push(Segment::Key(key.to_string()));
+                rest = after;
+            } else if let Some(key) = rest.strip_prefix('.') {
+                let end = key.find(&['.', '['][..]);
+                let (key, after) = key.split_at(end.unwrap_or(key.len()));
+                segments.push(match key {
+                    "" => return Err(invalid("empty key")),
+                    "*" => Segment::Any,
+                    key => Segment::Key(key.to_string()),
+                });
+                rest = after;
+            } else {
+                return Err(invalid("segments start with . or ["));
+            }
+        }
+
+        Ok(Self { segments })
+    }
+
+    fn matches(&self, path: &[Step]) -> bool {
+        self.segments.len() == path.len()
+            && self
+                .segments
+                .iter()
+                .zip(path)
+                .all(|(segment, step)| match (segment, step) {
+                    (Segment::Any, _) => true,
+                    (Segment::Key(key), Step::Key(step)) => key == step,
+                    (Segment::Index(index), Step::Index(step)) => index == step,
+                    _ => false,
+                })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ValueCondition {
+    /// Scalars written the same way, regardless of case
+    Equals(String),
+    GreaterThan(f64),
+    GreaterOrEqual(f64),
+    LessThan(f64),
+    LessOrEqual(f64),
+}
+
+impl ValueCondition {
+    fn parse(condition: &str) -> Result<Self> {
+        let number = |number: &str| {
+            number.parse::<f64>().map_err(|_| {
+                anyhow!(
+                    "Invalid value condition: {}, {} isn't a number",
+                    condition,
+                    number
+                )
+            })
+        };
+
+        Ok(if let Some(limit) = condition.strip_prefix(">=") {
+            ValueCondition::GreaterOrEqual(number(limit)?)
+        } else if let Some(limit) = condition.strip_prefix("<=") {
+            ValueCondition::LessOrEqual(number(limit)?)
+        } else if let Some(limit) = condition.strip_prefix('>') {
+            ValueCondition::GreaterThan(number(limit)?)
+        } else if let Some(limit) = condition.strip_prefix('<') {
+            ValueCondition::LessThan(number(limit)?)
+        } else {
+            let expected = condition.strip_prefix('=').unwrap_or(condition);
+            ValueCondition::Equals(expected.to_lowercase())
+        })
+    }
+
+    fn matches(&self, value: &Value) -> bool {
+        if let ValueCondition::Equals(expected) = self {
+            let text = match value {
+                Value::String(text) => text.to_lowercase(),
+                Value::Number(_) | Value::Bool(_) | Value::Null => value.to_string(),
+                _ => return false,
+            };
+            return text == *expected;
+        }
+
+        let number = match value {
+            Value::Number(number) => number.as_f64().unwrap_or(f64::NAN),
+            _ => return false,
+        };
+        match self {
+            ValueCondition::GreaterThan(limit) => number > *limit,
+            ValueCondition::GreaterOrEqual(limit) => number >= *limit,
+            ValueCondition::LessThan(limit) => number < *limit,
+            ValueCondition::LessOrEqual(limit) => number <= *limit,
+            ValueCondition::Equals(_) => false,
+        }
+    }
+}
+
+/// Matches the nodes of JSON, YAML and TOML documents rather than their text,
+/// written as clauses like `path:.dependencies.serde`, `key:timeout` or
+/// `value:>30`. A node has to match every clause given.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StructuredQuery {
+    path: Option<PathExpr>,
+    key: Option<String>,
+    value: Option<ValueCondition>,
+}
+
+impl StructuredQuery {
+    pub fn parse(query: &str) -> Result<Self> {
+        let mut structured_query = Self::default();
+        for clause in query.split_whitespace() {
+            if let Some(path) = clause.strip_prefix("path:") {
+                structured_query.path = Some(PathExpr::parse(path)?);
+            } else if let Some(key) = clause.strip_prefix("key:") {
+                structured_query.key = Some(key.to_string());
+            } else if let Some(value) = clause.strip_prefix("value:") {
+                structured_query.value = Some(ValueCondition::parse(value)?);
+            } else {
+                return Err(anyhow!(
+                    "Unknown structured query clause: {}, use path:, key: or value:",
+                    clause
+                ));
+            }
+        }
+
+        Ok(structured_query)
+    }
+
+    pub fn with_path(self, path: PathExpr) -> Self {
+        Self {
+            path: Some(path),
+            ..self
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.path.is_none() && self.key.is_none() && self.value.is_none()
+    }
+
+    /// Every matching node as `document path = value`, one per line, with
+    /// matches covering the document paths
+    pub(crate) fn find(&self, document: &Value) -> (String, Vec<Match>) {
+        let mut contents = String::new();
+        let mut matches = Vec::new();
+        self.visit(document, &mut Vec::new(), &mut |path, value| {
+            let start = contents.len();
+            let path = render_path(path);
+            let _ = writeln!(contents, "{} = {}", path, render_value(value));
+            matches.push(Match {
+                pattern: 0,
+                start,
+                end: start + path.len(),
+                start_line: matches.len() + 1,
+                end_line: matches.len() + 1,
+            });
+        });
+        contents.pop();
+
+        (contents, matches)
+    }
+
+    fn visit<'document>(
+        &self,
+        value: &'document Value,
+        path: &mut Vec<Step<'document>>,
+        found: &mut impl FnMut(&[Step], &Value),
+    ) {
+        if !path.is_empty() && self.matches(path, value) {
+            found(path, value);
+        }
+
+        match value {
+            Value::Object(object) => {
+                for (key, child) in object {
+                    path.push(Step::Key(key));
+                    self.visit(child, path, found);
+                    path.pop();
+                }
+            }
+            Value::Array(array) => {
+                for (index, child) in array.iter().enumerate() {
+                    path.push(Step::Index(index));
+                    self.visit(child, path, found);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn matches(&self, path: &[Step], value: &Value) -> bool {
+        let path_matches = match &self.path {
+            Some(expr) => expr.matches(path),
+            None => true,
+        };
+        let key_matches = match (&self.key, path.last()) {
+            (Some(key), Some(Step::Key(last))) => last == key,
+            (Some(_), _) => false,
+            (None, _) => true,
+        };
+        let value_matches = match &self.value {
+            Some(condition) => condition.matches(value),
+            None => true,
+        };
+
+        path_matches && key_matches && value_matches
+    }
+}
+
+/// The file parsed as a document, or `None` if it isn't JSON, YAML or TOML
+pub(crate) fn parse_document(path: &Path, contents: &str) -> Option<Result<Value>> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    let document = match extension.as_str() {
+        "json" => serde_json::from_str(contents).map_err(anyhow::Error::from),
+        "yaml" | "yml" => serde_yaml::from_str(contents).map_err(anyhow::Error::from),
+        "toml" => toml::from_str(contents)
+            .map(from_toml)
+            .map_err(anyhow::Error::from),
+        _ => return None,
+    };
+
+    Some(document)
+}
+
+/// TOML dates have no JSON counterpart, they're kept as strings
+fn from_toml(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(text) => Value::String(text),
+        toml::Value::Integer(number) => Value::from(number),
+        toml::Value::Float(number) => Value::from(number),
+        toml::Value::Boolean(boolean) => Value::Bool(boolean),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(array) => Value::Array(array.into_iter().map(from_toml).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, from_toml(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn render_path(path: &[Step]) -> String {
+    path.iter()
+        .map(|step| match step {
+            Step::Key(key) if is_plain_key(key) => format!(".{}", key),
+            Step::Key(key) => format!(".{}", Value::from(*key)),
+            Step::Index(index) => format!("[{}]", index),
+        })
+        .collect()
+}
+
+fn is_plain_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|character| character.is_alphanumeric() || character == '_' || character == '-')
+}
+
+fn render_value(value: &Value) -> String {
+    let rendered = value.to_string();
+    match rendered.char_indices().nth(MAX_RENDERED_VALUE_LENGTH) {
+        Some((end, _)) => format!("{}…", &rendered[..end]),
+        None => rendered,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARGO_TOML: &str = r#"
+[package]
+name = "wicli"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+rayon = "1.5"
+"#;
+
+    const SERVICES_YAML: &str = "
+services:
+  api:
+    timeout: 45
+    image: api:latest
+  worker:
+    timeout: 10
+";
+
+    fn find(query: StructuredQuery, path: &str, contents: &str) -> String {
+        let document = parse_document(Path::new(path), contents).unwrap().unwrap();
+        query.find(&document).0
+    }
+
+    #[test]
+    fn it_parses_path_expressions() -> Result<()> {
+        assert_eq!(
+            PathExpr::parse(".servers[0].\"a.b\".*[*]")?.segments,
+            vec![
+                Segment::Key("servers".to_string()),
+                Segment::Index(0),
+                Segment::Key("a.b".to_string()),
+                Segment::Any,
+                Segment::Any,
+            ]
+        );
+        assert!(PathExpr::parse("servers").is_err());
+        assert!(PathExpr::parse(".servers[x]").is_err());
+        assert!(StructuredQuery::parse("timeout").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_finds_nodes_by_path() -> Result<()> {
+        let query = StructuredQuery::default().with_path(PathExpr::parse(".dependencies.serde")?);
+
+        assert_eq!(
+            find(query, "Cargo.toml", CARGO_TOML),
+            r#".dependencies.serde = {"features":["derive"],"version":"1.0"}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_finds_nodes_by_key_and_value() -> Result<()> {
+        let query = StructuredQuery::parse("key:timeout value:>30")?;
+
+        assert_eq!(
+            find(query, "services.yml", SERVICES_YAML),
+            ".services.api.timeout = 45"
+        );
+
+        let query = StructuredQuery::parse("path:.services.*.image value:API:LATEST")?;
+
+        assert_eq!(
+            find(query, "services.yml", SERVICES_YAML),
+            ".services.api.image = \"api:latest\""
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_highlights_the_document_paths() -> Result<()> {
+        let document = serde_json::from_str(r#"{"a": [1, 2], "b c": 3}"#)?;
+
+        let (contents, matches) = StructuredQuery::parse("value:>1")?.find(&document);
+
+        assert_eq!(contents, ".a[1] = 2\n.\"b c\" = 3");
+        let highlighted = matches
+            .iter()
+            .map(|found| &contents[found.start..found.end])
+            .collect::<Vec<&str>>();
+        assert_eq!(highlighted, vec![".a[1]", ".\"b c\""]);
+        assert!(parse_document(Path::new("notes.md"), "# a").is_none());
+        Ok(())
+    }
+}
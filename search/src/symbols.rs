@@ -0,0 +1,548 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use config::Config;
+use rayon::prelude::*;
+use serde::Serialize;
+use tree_sitter::{Node, Tree};
+
+use crate::{
+    diagnostics::{Diagnostic, Diagnostics},
+    syntax::{text, Syntax},
+    walker::Walker,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Class,
+    Struct,
+    Enum,
+    Trait,
+    Interface,
+    Type,
+    Constant,
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            SymbolKind::Function => "function",
+            SymbolKind::Method => "method",
+            SymbolKind::Class => "class",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Trait => "trait",
+            SymbolKind::Interface => "interface",
+            SymbolKind::Type => "type",
+            SymbolKind::Constant => "constant",
+        };
+        formatter.write_str(label)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Symbol {
+    pub source: PathBuf,
+    pub path: PathBuf,
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Modules, types and functions the symbol is defined in, like `Config::load`
+    pub scope: Option<String>,
+    pub line: usize,
+    /// First line of the definition
+    pub definition: String,
+}
+
+/// Definitions found, along with the files that couldn't be read
+#[derive(Debug, Serialize)]
+pub struct SymbolReport {
+    pub symbols: Vec<Symbol>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScopeKind {
+    Module,
+    Type,
+    Function,
+}
+
+#[derive(Debug)]
+struct Scope {
+    name: String,
+    kind: ScopeKind,
+}
+
+#[derive(Debug, PartialEq)]
+struct Definition {
+    kind: SymbolKind,
+    scope: Option<String>,
+    line: usize,
+}
+
+/// Finds where functions, types and constants are defined in Rust,
+/// TypeScript, Python and Go files, leaving out every place they're used
+pub struct Symbols;
+
+impl Symbols {
+    pub fn find(config: &Config, name: &str) -> SymbolReport {
+        let diagnostics = Diagnostics::default();
+        let mut symbols = config
+            .config_map
+            .sources
+            .par_iter()
+            .flat_map(|source_path| {
+                Walker::source_files_kept(source_path, is_parsed, &diagnostics)
+                    .flatten()
+                    .flat_map(move |source_file| {
+                        let definitions = match Syntax::from_path(&source_file.path) {
+                            Some(syntax) => Self::definitions(syntax, &source_file.contents, name),
+                            None => Vec::new(),
+                        };
+                        definitions
+                            .into_iter()
+                            .map(|definition| Symbol {
+                                source: source_path.clone(),
+                                path: source_file.path.clone(),
+                                name: name.to_string(),
+                                kind: definition.kind,
+                                scope: definition.scope,
+                                line: definition.line,
+                                definition: source_file
+                                    .contents
+                                    .lines()
+                                    .nth(definition.line - 1)
+                                    .unwrap_or_default()
+                                    .trim()
+                                    .to_string(),
+                            })
+                            .collect::<Vec<Symbol>>()
+                    })
+            })
+            .collect::<Vec<Symbol>>();
+        symbols.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+
+        SymbolReport {
+            symbols,
+            diagnostics: diagnostics.into_sorted(),
+        }
+    }
+
+    fn definitions(syntax: Syntax, contents: &str, name: &str) -> Vec<Definition> {
+        let tree = match syntax.parse(contents) {
+            Some(tree) => tree,
+            None => return Vec::new(),
+        };
+        let mut collector = Collector {
+            syntax,
+            contents,
+            name,
+            scopes: Vec::new(),
+            found: Vec::new(),
+        };
+        collector.visit(&tree);
+
+        collector.found
+    }
+}
+
+/// Only the files a syntax tree can be parsed for are read
+fn is_parsed(name: &str, is_dir: bool) -> bool {
+    is_dir || Syntax::from_path(Path::new(name)).is_some()
+}
+
+struct Collector<'contents> {
+    syntax: Syntax,
+    contents: &'contents str,
+    name: &'contents str,
+    scopes: Vec<Scope>,
+    found: Vec<Definition>,
+}
+
+impl<'contents> Collector<'contents> {
+    /// Walks the tree with a cursor rather than recursing, so deeply nested
+    /// code can't overflow the stack of the thread
+    fn visit(&mut self, tree: &Tree) {
+        let mut cursor = tree.walk();
+        // How many scopes each node of the current path opened
+        let mut opened = Vec::new();
+        let mut visited_children = false;
+        loop {
+            if !visited_children {
+                let node = cursor.node();
+                if !node.is_named() {
+                    opened.push(0);
+                } else {
+                    opened.push(self.enter(node));
+                    if cursor.goto_first_child() {
+                        continue;
+                    }
+                }
+            }
+
+            let scopes = opened.pop().unwrap_or_default();
+            self.scopes.truncate(self.scopes.len() - scopes);
+            if cursor.goto_next_sibling() {
+                visited_children = false;
+            } else if cursor.goto_parent() {
+                visited_children = true;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Collects what the node defines, then opens its scopes. Returns how many
+    /// it opened, they're closed once the walk leaves the node.
+    fn enter(&mut self, node: Node) -> usize {
+        let scopes = self.scopes.len();
+        // Go methods are declared outside of their type, they're scoped to the receiver
+        if let ("method_declaration", Syntax::Go) = (node.kind(), self.syntax) {
+            self.scopes.extend(self.receiver(node));
+        }
+
+        for (name, kind) in self.defined(node) {
+            let inside_function = matches!(
+                self.scopes.last(),
+                Some(Scope {
+                    kind: ScopeKind::Function,
+                    ..
+                })
+            );
+            if name == self.name && !(kind == SymbolKind::Constant && inside_function) {
+                self.found.push(Definition {
+                    kind,
+                    scope: self.scope(),
+                    line: node.start_position().row + 1,
+                });
+            }
+        }
+
+        self.scopes.extend(self.opened(node));
+
+        self.scopes.len() - scopes
+    }
+
+    /// Names the node defines, with what they are
+    fn defined(&self, node: Node) -> Vec<(&'contents str, SymbolKind)> {
+        let in_type = matches!(
+            self.scopes.last(),
+            Some(Scope {
+                kind: ScopeKind::Type,
+                ..
+            })
+        );
+        let function = match in_type {
+            true => SymbolKind::Method,
+            false => SymbolKind::Function,
+        };
+
+        let kind = match (self.syntax, node.kind()) {
+            (Syntax::Rust, "function_item") | (Syntax::Rust, "function_signature_item") => function,
+            (Syntax::Rust, "struct_item") | (Syntax::Rust, "union_item") => SymbolKind::Struct,
+            (Syntax::Rust, "enum_item") => SymbolKind::Enum,
+            (Syntax::Rust, "trait_item") => SymbolKind::Trait,
+            (Syntax::Rust, "type_item") => SymbolKind::Type,
+            (Syntax::Rust, "const_item") | (Syntax::Rust, "static_item") => SymbolKind::Constant,
+            (Syntax::TypeScript, kind) | (Syntax::Tsx, kind) => match kind {
+                "function_declaration" | "generator_function_declaration" => SymbolKind::Function,
+                "method_definition" | "method_signature" | "abstract_method_signature" => {
+                    SymbolKind::Method
+                }
+                "class_declaration" | "abstract_class_declaration" => SymbolKind::Class,
+                "interface_declaration" => SymbolKind::Interface,
+                "type_alias_declaration" => SymbolKind::Type,
+                "enum_declaration" => SymbolKind::Enum,
+                "lexical_declaration" => return self.typescript_constants(node),
+                _ => return Vec::new(),
+            },
+            (Syntax::Python, "function_definition") => function,
+            (Syntax::Python, "class_definition") => SymbolKind::Class,
+            (Syntax::Python, "assignment") => return self.python_constant(node),
+            (Syntax::Go, "function_declaration") => SymbolKind::Function,
+            (Syntax::Go, "method_declaration") => SymbolKind::Method,
+            (Syntax::Go, "type_spec") | (Syntax::Go, "type_alias") => {
+                match node
+                    .child_by_field_name("type")
+                    .map(|type_node| type_node.kind())
+                {
+                    Some("struct_type") => SymbolKind::Struct,
+                    Some("interface_type") => SymbolKind::Interface,
+                    _ => SymbolKind::Type,
+                }
+            }
+            (Syntax::Go, "const_spec") => {
+                let mut cursor = node.walk();
+                return node
+                    .children_by_field_name("name", &mut cursor)
+                    .map(|name| (text(name, self.contents), SymbolKind::Constant))
+                    .collect();
+            }
+            _ => return Vec::new(),
+        };
+
+        match node.child_by_field_name("name") {
+            Some(name) => vec![(text(name, self.contents), kind)],
+            None => Vec::new(),
+        }
+    }
+
+    /// Declarators of `const` declarations, the ones holding functions count as functions
+    fn typescript_constants(&self, node: Node) -> Vec<(&'contents str, SymbolKind)> {
+        let mut cursor = node.walk();
+        if node.child(0).map(|keyword| keyword.kind()) != Some("const") {
+            return Vec::new();
+        }
+
+        node.named_children(&mut cursor)
+            .filter(|declarator| declarator.kind() == "variable_declarator")
+            .filter_map(|declarator| {
+                let name = declarator.child_by_field_name("name")?;
+                if name.kind() != "identifier" {
+                    return None;
+                }
+                let kind = match declarator
+                    .child_by_field_name("value")
+                    .map(|value| value.kind())
+                {
+                    Some("arrow_function") | Some("function") | Some("function_expression") => {
+                        SymbolKind::Function
+                    }
+                    _ => SymbolKind::Constant,
+                };
+                Some((text(name, self.contents), kind))
+            })
+            .collect()
+    }
+
+    /// Python has no constants, names written in capitals are by convention
+    fn python_constant(&self, node: Node) -> Vec<(&'contents str, SymbolKind)> {
+        let name = match node.child_by_field_name("left") {
+            Some(left) if left.kind() == "identifier" => text(left, self.contents),
+            _ => return Vec::new(),
+        };
+        let is_constant = name.chars().any(|character| character.is_ascii_uppercase())
+            && !name.chars().any(|character| character.is_lowercase());
+
+        match is_constant {
+            true => vec![(name, SymbolKind::Constant)],
+            false => Vec::new(),
+        }
+    }
+
+    /// Scope the node opens for what's defined inside of it
+    fn opened(&self, node: Node) -> Option<Scope> {
+        let (name_field, kind) = match (self.syntax, node.kind()) {
+            (Syntax::Rust, "mod_item") => ("name", ScopeKind::Module),
+            (Syntax::Rust, "impl_item") => ("type", ScopeKind::Type),
+            (Syntax::Rust, "trait_item") => ("name", ScopeKind::Type),
+            (Syntax::Rust, "function_item") => ("name", ScopeKind::Function),
+            (Syntax::TypeScript, kind) | (Syntax::Tsx, kind) => match kind {
+                "internal_module" | "module" => ("name", ScopeKind::Module),
+                "class_declaration" | "abstract_class_declaration" | "interface_declaration" => {
+                    ("name", ScopeKind::Type)
+                }
+                "function_declaration" | "generator_function_declaration" | "method_definition" => {
+                    ("name", ScopeKind::Function)
+                }
+                _ => return None,
+            },
+            (Syntax::Python, "class_definition") => ("name", ScopeKind::Type),
+            (Syntax::Python, "function_definition") => ("name", ScopeKind::Function),
+            (Syntax::Go, "function_declaration") | (Syntax::Go, "method_declaration") => {
+                ("name", ScopeKind::Function)
+            }
+            _ => return None,
+        };
+
+        Some(Scope {
+            name: text(node.child_by_field_name(name_field)?, self.contents).to_string(),
+            kind,
+        })
+    }
+
+    /// Type of the receiver of a Go method, without the pointer
+    fn receiver(&self, node: Node) -> Option<Scope> {
+        let receiver = node.child_by_field_name("receiver")?;
+        let mut cursor = receiver.walk();
+        let parameter = receiver
+            .named_children(&mut cursor)
+            .find(|parameter| parameter.kind() == "parameter_declaration")?;
+        let type_name = text(parameter.child_by_field_name("type")?, self.contents);
+
+        Some(Scope {
+            name: type_name.trim_start_matches('*').to_string(),
+            kind: ScopeKind::Type,
+        })
+    }
+
+    fn scope(&self) -> Option<String> {
+        match self.scopes.is_empty() {
+            true => None,
+            false => Some(
+                self.scopes
+                    .iter()
+                    .map(|scope| scope.name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(self.syntax.scope_separator()),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use anyhow::Result;
+    use config::Configuration;
+    use tempfile::tempdir;
+
+    fn found(syntax: Syntax, contents: &str, name: &str) -> Vec<(SymbolKind, Option<String>)> {
+        Symbols::definitions(syntax, contents, name)
+            .into_iter()
+            .map(|definition| (definition.kind, definition.scope))
+            .collect()
+    }
+
+    #[test]
+    fn it_finds_rust_definitions_with_their_scope() {
+        let contents = "
+mod config {
+    pub const LOAD: u8 = 1;
+    pub struct Config;
+    impl Config {
+        pub fn load() -> Self {
+            const LOAD: u8 = 2;
+            load();
+            Config
+        }
+    }
+}
+fn load() {}
+";
+
+        assert_eq!(
+            found(Syntax::Rust, contents, "load"),
+            vec![
+                (SymbolKind::Method, Some("config::Config".to_string())),
+                (SymbolKind::Function, None),
+            ]
+        );
+        assert_eq!(
+            found(Syntax::Rust, contents, "LOAD"),
+            vec![(SymbolKind::Constant, Some("config".to_string()))]
+        );
+    }
+
+    #[test]
+    fn it_finds_typescript_python_and_go_definitions() {
+        let typescript = "
+export interface Source { path: string }
+export class Search {
+  run(source: Source) { return run(source); }
+}
+export const run = (source: Source) => source;
+const MAX_RESULTS = 10;
+";
+        assert_eq!(
+            found(Syntax::TypeScript, typescript, "run"),
+            vec![
+                (SymbolKind::Method, Some("Search".to_string())),
+                (SymbolKind::Function, None),
+            ]
+        );
+        assert_eq!(
+            found(Syntax::TypeScript, typescript, "Source"),
+            vec![(SymbolKind::Interface, None)]
+        );
+
+        let python = "
+MAX_RESULTS = 10
+
+class Search:
+    def run(self):
+        MAX_RESULTS = 20
+        return run()
+
+def run():
+    pass
+";
+        assert_eq!(
+            found(Syntax::Python, python, "run"),
+            vec![
+                (SymbolKind::Method, Some("Search".to_string())),
+                (SymbolKind::Function, None),
+            ]
+        );
+        assert_eq!(
+            found(Syntax::Python, python, "MAX_RESULTS"),
+            vec![(SymbolKind::Constant, None)]
+        );
+
+        let go = "
+package search
+
+const MaxResults = 10
+
+type Search struct{}
+
+func (s *Search) Run() error { return Run() }
+
+func Run() error { return nil }
+";
+        assert_eq!(
+            found(Syntax::Go, go, "Run"),
+            vec![
+                (SymbolKind::Method, Some("Search".to_string())),
+                (SymbolKind::Function, None),
+            ]
+        );
+        assert_eq!(
+            found(Syntax::Go, go, "Search"),
+            vec![(SymbolKind::Struct, None)]
+        );
+    }
+
+    #[test]
+    fn it_walks_deeply_nested_code() {
+        let depth = 50_000;
+        let contents = format!(
+            "fn outer() {{ {}fn load() {{}}{} }}",
+            "{ ".repeat(depth),
+            " }".repeat(depth)
+        );
+
+        assert_eq!(
+            found(Syntax::Rust, &contents, "load"),
+            vec![(SymbolKind::Function, Some("outer".to_string()))]
+        );
+    }
+
+    #[test]
+    fn it_finds_symbols_across_sources() -> Result<()> {
+        let source = tempdir()?;
+        fs::write(
+            source.path().join("lib.rs"),
+            "pub fn load() {}\nfn main() { load(); }",
+        )?;
+        fs::write(source.path().join("notes.md"), "fn load() {}")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+
+        let symbols = Symbols::find(&config, "load").symbols;
+
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].path, source.path().join("lib.rs"));
+        assert_eq!(symbols[0].line, 1);
+        assert_eq!(symbols[0].definition, "pub fn load() {}");
+        Ok(())
+    }
+}
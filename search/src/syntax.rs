@@ -0,0 +1,195 @@
+use std::{ops::Range, path::Path, str::FromStr};
+
+use anyhow::anyhow;
+use tree_sitter::{Language, Node, Parser, Tree};
+
+/// Node kinds of string literals across the grammars, the ones of characters aren't strings
+const STRING_KINDS: &[&str] = &[
+    "string",
+    "string_literal",
+    "raw_string_literal",
+    "interpreted_string_literal",
+    "template_string",
+];
+
+/// Part of source files a search can be limited to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyntaxScope {
+    Comments,
+    Strings,
+    /// Everything but comments and string literals
+    Code,
+}
+
+impl FromStr for SyntaxScope {
+    type Err = anyhow::Error;
+
+    fn from_str(scope: &str) -> Result<Self, Self::Err> {
+        match scope {
+            "comments" => Ok(SyntaxScope::Comments),
+            "strings" => Ok(SyntaxScope::Strings),
+            "code" => Ok(SyntaxScope::Code),
+            _ => Err(anyhow!("Unknown scope: {}", scope)),
+        }
+    }
+}
+
+impl SyntaxScope {
+    pub(crate) fn contains(self, literals: &Literals, found: Range<usize>) -> bool {
+        let inside = |ranges: &[Range<usize>]| {
+            ranges
+                .iter()
+                .any(|range| range.start <= found.start && found.end <= range.end)
+        };
+        let overlaps = |ranges: &[Range<usize>]| {
+            ranges
+                .iter()
+                .any(|range| range.start < found.end && found.start < range.end)
+        };
+
+        match self {
+            SyntaxScope::Comments => inside(&literals.comments),
+            SyntaxScope::Strings => inside(&literals.strings),
+            SyntaxScope::Code => !overlaps(&literals.comments) && !overlaps(&literals.strings),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Literals {
+    comments: Vec<Range<usize>>,
+    strings: Vec<Range<usize>>,
+}
+
+/// Languages whose files can be parsed into a syntax tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Syntax {
+    Rust,
+    TypeScript,
+    Tsx,
+    Python,
+    Go,
+}
+
+impl Syntax {
+    pub(crate) fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "rs" => Some(Syntax::Rust),
+            "ts" | "mts" | "cts" => Some(Syntax::TypeScript),
+            "tsx" => Some(Syntax::Tsx),
+            "py" | "pyi" => Some(Syntax::Python),
+            "go" => Some(Syntax::Go),
+            _ => None,
+        }
+    }
+
+    /// Separator of nested scopes when they're written out, like `Config::load`
+    pub(crate) fn scope_separator(self) -> &'static str {
+        match self {
+            Syntax::Rust => "::",
+            _ => ".",
+        }
+    }
+
+    pub(crate) fn parse(self, contents: &str) -> Option<Tree> {
+        let mut parser = Parser::new();
+        parser.set_language(self.language()).ok()?;
+        parser.parse(contents, None)
+    }
+
+    /// Where the comments and string literals of the file are
+    pub(crate) fn literals(self, contents: &str) -> Option<Literals> {
+        let tree = self.parse(contents)?;
+        let mut literals = Literals::default();
+        let mut cursor = tree.walk();
+        let mut visited_children = false;
+        loop {
+            let node = cursor.node();
+            let kind = node.kind();
+            let literal = if kind.ends_with("comment") {
+                Some(&mut literals.comments)
+            } else if STRING_KINDS.contains(&kind) {
+                Some(&mut literals.strings)
+            } else {
+                None
+            };
+            if let (Some(ranges), false) = (literal, visited_children) {
+                ranges.push(node.byte_range());
+                visited_children = true;
+            }
+
+            if !visited_children && cursor.goto_first_child() {
+                continue;
+            }
+            if cursor.goto_next_sibling() {
+                visited_children = false;
+            } else if cursor.goto_parent() {
+                visited_children = true;
+            } else {
+                break;
+            }
+        }
+
+        Some(literals)
+    }
+
+    fn language(self) -> Language {
+        match self {
+            Syntax::Rust => tree_sitter_rust::language(),
+            Syntax::TypeScript => tree_sitter_typescript::language_typescript(),
+            Syntax::Tsx => tree_sitter_typescript::language_tsx(),
+            Syntax::Python => tree_sitter_python::language(),
+            Syntax::Go => tree_sitter_go::language(),
+        }
+    }
+}
+
+pub(crate) fn text<'contents>(node: Node, contents: &'contents str) -> &'contents str {
+    &contents[node.byte_range()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUST: &str = "// TODO: load lazily\nfn load() -> &'static str { \"TODO\" }";
+
+    fn scopes_of(syntax: Syntax, contents: &str, word: &str) -> Vec<SyntaxScope> {
+        let literals = syntax.literals(contents).unwrap();
+        contents
+            .match_indices(word)
+            .flat_map(|(start, _)| {
+                [
+                    SyntaxScope::Comments,
+                    SyntaxScope::Strings,
+                    SyntaxScope::Code,
+                ]
+                .iter()
+                .copied()
+                .filter(|scope| scope.contains(&literals, start..start + word.len()))
+                .collect::<Vec<SyntaxScope>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_tells_comments_strings_and_code_apart() {
+        assert_eq!(
+            scopes_of(Syntax::Rust, RUST, "TODO"),
+            vec![SyntaxScope::Comments, SyntaxScope::Strings]
+        );
+        assert_eq!(
+            scopes_of(Syntax::Rust, RUST, "load"),
+            vec![SyntaxScope::Comments, SyntaxScope::Code]
+        );
+        assert_eq!(
+            scopes_of(Syntax::Python, "# retry\nretry(\"retry\")", "retry"),
+            vec![
+                SyntaxScope::Comments,
+                SyntaxScope::Code,
+                SyntaxScope::Strings
+            ]
+        );
+    }
+}
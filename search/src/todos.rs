@@ -0,0 +1,204 @@
+use std::path::{Path, PathBuf};
+
+use config::Config;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::{
+    diagnostics::{Diagnostic, Diagnostics},
+    git,
+    walker::Walker,
+};
+
+/// A marker as a word of its own, with an optional `(owner)` and `:` after it
+const MARKER_PATTERN: &str = r"\b(TODO|FIXME|HACK|XXX)\b(?:\([^)\n]*\))?:?[ \t]*([^\r\n]*)";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Todo {
+    pub path: PathBuf,
+    pub line: usize,
+    /// `TODO`, `FIXME`, `HACK` or `XXX`
+    pub marker: String,
+    pub text: String,
+    /// Who last changed the line, for git sources
+    pub author: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TodoGroup {
+    pub source: PathBuf,
+    pub todos: Vec<Todo>,
+}
+
+/// Markers found in each source, along with the files that couldn't be read
+#[derive(Debug, Serialize)]
+pub struct TodoReport {
+    pub groups: Vec<TodoGroup>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Collects the TODO, FIXME, HACK and XXX markers left across the sources
+pub struct Todos;
+
+impl Todos {
+    pub fn find(config: &Config) -> TodoReport {
+        let marker = Regex::new(MARKER_PATTERN).expect("The marker pattern is valid");
+        let diagnostics = Diagnostics::default();
+
+        let groups = config
+            .config_map
+            .sources
+            .iter()
+            .map(|source_path| {
+                let is_repository = git::is_repository(source_path);
+                let marker = &marker;
+                let mut todos = Walker::source_files(source_path, None, &diagnostics)
+                    .flatten()
+                    .flat_map(|source_file| {
+                        let mut todos = Self::markers(marker, &source_file.contents);
+                        for todo in &mut todos {
+                            todo.path = source_file.path.clone();
+                        }
+                        if is_repository && !todos.is_empty() {
+                            Self::attribute(source_path, &mut todos);
+                        }
+                        todos
+                    })
+                    .collect::<Vec<Todo>>();
+                todos.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+
+                TodoGroup {
+                    source: source_path.clone(),
+                    todos,
+                }
+            })
+            .filter(|group| !group.todos.is_empty())
+            .collect();
+
+        TodoReport {
+            groups,
+            diagnostics: diagnostics.into_sorted(),
+        }
+    }
+
+    fn markers(marker: &Regex, contents: &str) -> Vec<Todo> {
+        contents
+            .lines()
+            .enumerate()
+            .flat_map(|(index, line)| {
+                marker.captures_iter(line).map(move |captures| Todo {
+                    path: PathBuf::new(),
+                    line: index + 1,
+                    marker: captures[1].to_string(),
+                    text: captures[2]
+                        .trim()
+                        .trim_end_matches("*/")
+                        .trim_end_matches("-->")
+                        .trim_end()
+                        .to_string(),
+                    author: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Files git doesn't track yet are left without authors
+    fn attribute(source_path: &Path, todos: &mut [Todo]) {
+        let authors = match git::blame(source_path, &todos[0].path) {
+            Ok(authors) => authors,
+            Err(_) => return,
+        };
+        for todo in todos {
+            todo.author = authors.get(todo.line - 1).cloned().flatten();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, process::Command};
+
+    use super::*;
+    use crate::DiagnosticKind;
+    use anyhow::Result;
+    use config::Configuration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn it_finds_the_markers_with_their_text() {
+        let marker = Regex::new(MARKER_PATTERN).unwrap();
+        let contents =
+            "/* TODO(ana): cache this */\nlet TODO_LIST = 1; // FIXME\n# XXX: why? HACK around it";
+
+        let found = Todos::markers(&marker, contents)
+            .into_iter()
+            .map(|todo| (todo.line, todo.marker, todo.text))
+            .collect::<Vec<(usize, String, String)>>();
+
+        assert_eq!(
+            found,
+            vec![
+                (1, "TODO".to_string(), "cache this".to_string()),
+                (2, "FIXME".to_string(), String::new()),
+                (3, "XXX".to_string(), "why? HACK around it".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_the_files_it_cannot_read() -> Result<()> {
+        let source = tempdir()?;
+        fs::write(source.path().join("main.rs"), "// TODO: split main\n")?;
+        fs::write(source.path().join("logo.png"), [0x89, 0x50, 0xff, 0xfe])?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+
+        let report = Todos::find(&config);
+
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(report.diagnostics[0].kind, DiagnosticKind::NotUtf8);
+        assert_eq!(report.diagnostics[0].path, source.path().join("logo.png"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_attributes_the_markers_to_their_git_author() -> Result<()> {
+        let source = tempdir()?;
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(["-c", "user.name=Ada", "-c", "user.email=ada@example.com"])
+                .args(args)
+                .current_dir(source.path())
+                .output()
+        };
+        git(&["init", "--quiet"])?;
+        fs::write(source.path().join("main.rs"), "// TODO: split main\n")?;
+        git(&["add", "."])?;
+        git(&["commit", "--quiet", "-m", "main"])?;
+        fs::write(
+            source.path().join("main.rs"),
+            "// TODO: split main\n// FIXME: not committed\n",
+        )?;
+        fs::write(source.path().join("notes.txt"), "HACK: untracked")?;
+        let fake_config_dir = tempdir()?;
+        let mut config = Config::load_or_create(fake_config_dir.into_path())?;
+        config.add_source(source.path())?;
+
+        let groups = Todos::find(&config).groups;
+
+        assert_eq!(groups.len(), 1);
+        let found = groups[0]
+            .todos
+            .iter()
+            .map(|todo| (todo.marker.as_str(), todo.author.as_deref()))
+            .collect::<Vec<(&str, Option<&str>)>>();
+        assert_eq!(
+            found,
+            vec![("TODO", Some("Ada")), ("FIXME", None), ("HACK", None)]
+        );
+        Ok(())
+    }
+}
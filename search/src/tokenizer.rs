@@ -0,0 +1,74 @@
+/// A word of the text, lowercased, along with where it was found. Shared by
+/// everything that reasons about words rather than raw substrings, so queries
+/// and indexed text always agree on what a word is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub term: String,
+    /// Byte offsets into the original text
+    pub start: usize,
+    pub end: usize,
+    /// Index of the token in the text, counting words rather than bytes
+    pub position: usize,
+}
+
+/// Splits on anything that isn't alphanumeric or an underscore, so identifiers
+/// like `max_retries` stay a single word
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word_start = None;
+
+    for (offset, character) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        let is_word_character = character.is_alphanumeric() || character == '_';
+        match (word_start, is_word_character) {
+            (None, true) => word_start = Some(offset),
+            (Some(start), false) => {
+                tokens.push(Token {
+                    term: text[start..offset].to_lowercase(),
+                    start,
+                    end: offset,
+                    position: tokens.len(),
+                });
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_splits_words_keeping_their_offsets_and_positions() {
+        let tokens = tokenize("Set max_retries, then Deploy!");
+
+        let terms = tokens
+            .iter()
+            .map(|token| (token.term.as_str(), token.start, token.end, token.position))
+            .collect::<Vec<(&str, usize, usize, usize)>>();
+        assert_eq!(
+            terms,
+            vec![
+                ("set", 0, 3, 0),
+                ("max_retries", 4, 15, 1),
+                ("then", 17, 21, 2),
+                ("deploy", 22, 28, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_keeps_non_ascii_words_whole() {
+        let tokens = tokenize("configuração é");
+
+        assert_eq!(tokens[0].term, "configuração");
+        assert_eq!(tokens[0].end, "configuração".len());
+        assert_eq!(tokens[1].term, "é");
+    }
+}
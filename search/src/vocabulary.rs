@@ -0,0 +1,238 @@
+use std::{collections::HashMap, path::Path};
+
+use config::Config;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diagnostics::Diagnostics,
+    limits::{Budget, Limits},
+    tokenizer::tokenize,
+    walker::Walker,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    /// Index of the search pattern this term would replace
+    pub pattern: usize,
+    pub term: String,
+    pub distance: usize,
+    pub frequency: usize,
+}
+
+/// Every word and file name found in the sources, as written, and how often they show up
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Vocabulary {
+    frequencies: HashMap<String, usize>,
+    file_names: HashMap<String, usize>,
+}
+
+impl Vocabulary {
+    /// Words of every source. Files that can't be read are left out, the
+    /// search reports them.
+    pub fn build(config: &Config, git_ref: Option<&str>) -> Self {
+        Self::build_within(config, git_ref, Limits::default())
+    }
+
+    /// Words of the files of every source a search with these limits reads
+    pub(crate) fn build_within(config: &Config, git_ref: Option<&str>, limits: Limits) -> Self {
+        let budget = Budget::new(limits);
+        let diagnostics = Diagnostics::default();
+        config
+            .config_map
+            .sources
+            .par_iter()
+            .flat_map(|source_path| {
+                Walker::source_files_within(source_path, git_ref, &budget, &diagnostics)
+            })
+            .flatten()
+            .fold(Self::default, |mut vocabulary, source_file| {
+                vocabulary.add_file(&source_file.path, &source_file.contents);
+                vocabulary
+            })
+            .reduce(Self::default, Self::merge)
+    }
+
+    pub fn add(&mut self, text: &str) {
+        Self::count(&mut self.frequencies, text);
+    }
+
+    pub fn add_file(&mut self, path: &Path, contents: &str) {
+        if let Some(file_name) = path.file_name() {
+            *self
+                .file_names
+                .entry(file_name.to_string_lossy().into_owned())
+                .or_default() += 1;
+        }
+        self.add(contents);
+    }
+
+    /// Words starting with the prefix regardless of case, most frequent first
+    pub fn words_starting_with(&self, prefix: &str) -> Vec<&str> {
+        Self::starting_with(&self.frequencies, prefix)
+    }
+
+    /// File names starting with the prefix regardless of case, most frequent first
+    pub fn file_names_starting_with(&self, prefix: &str) -> Vec<&str> {
+        Self::starting_with(&self.file_names, prefix)
+    }
+
+    /// Words close to the given one, closest and then most frequent first
+    pub fn suggest(&self, word: &str, limit: usize) -> Vec<(String, usize, usize)> {
+        let word_length = word.chars().count();
+        let max_distance = if word_length < 5 { 1 } else { 2 };
+
+        let mut candidates = self
+            .frequencies
+            .iter()
+            .filter(|(candidate, _)| {
+                let length = candidate.chars().count();
+                length + max_distance >= word_length && length <= word_length + max_distance
+            })
+            .map(|(candidate, frequency)| {
+                let distance = strsim::levenshtein(word, candidate);
+                (candidate.clone(), distance, *frequency)
+            })
+            .filter(|(_, distance, _)| *distance > 0 && *distance <= max_distance)
+            .collect::<Vec<(String, usize, usize)>>();
+        candidates.sort_by(
+            |(term_a, distance_a, frequency_a), (term_b, distance_b, frequency_b)| {
+                distance_a
+                    .cmp(distance_b)
+                    .then(frequency_b.cmp(frequency_a))
+                    .then(term_a.cmp(term_b))
+            },
+        );
+        candidates.truncate(limit);
+
+        candidates
+    }
+
+    /// Suggestions for every pattern that is a single word, at most `limit` overall
+    pub fn suggest_patterns(&self, patterns: &[String], limit: usize) -> Vec<Suggestion> {
+        let mut suggestions = patterns
+            .iter()
+            .enumerate()
+            .filter(|(_, pattern)| Self::is_single_word(pattern))
+            .flat_map(|(index, pattern)| {
+                self.suggest(pattern, limit)
+                    .into_iter()
+                    .map(move |(term, distance, frequency)| Suggestion {
+                        pattern: index,
+                        term,
+                        distance,
+                        frequency,
+                    })
+            })
+            .collect::<Vec<Suggestion>>();
+        suggestions.sort_by(|a, b| {
+            a.distance
+                .cmp(&b.distance)
+                .then(b.frequency.cmp(&a.frequency))
+        });
+        suggestions.truncate(limit);
+
+        suggestions
+    }
+
+    fn starting_with<'vocabulary>(
+        frequencies: &'vocabulary HashMap<String, usize>,
+        prefix: &str,
+    ) -> Vec<&'vocabulary str> {
+        let prefix = prefix.to_lowercase();
+        let mut found = frequencies
+            .iter()
+            .filter(|(word, _)| {
+                word.len() > prefix.len() && word.to_lowercase().starts_with(&prefix)
+            })
+            .collect::<Vec<(&String, &usize)>>();
+        found.sort_by(|(word_a, frequency_a), (word_b, frequency_b)| {
+            frequency_b.cmp(frequency_a).then(word_a.cmp(word_b))
+        });
+
+        found.into_iter().map(|(word, _)| word.as_str()).collect()
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        for (word, frequency) in other.frequencies {
+            *self.frequencies.entry(word).or_default() += frequency;
+        }
+        for (file_name, frequency) in other.file_names {
+            *self.file_names.entry(file_name).or_default() += frequency;
+        }
+        self
+    }
+
+    fn is_single_word(pattern: &str) -> bool {
+        match tokenize(pattern).as_slice() {
+            [token] => token.start == 0 && token.end == pattern.len(),
+            _ => false,
+        }
+    }
+
+    fn count(frequencies: &mut HashMap<String, usize>, text: &str) {
+        for token in tokenize(text) {
+            *frequencies
+                .entry(text[token.start..token.end].to_string())
+                .or_default() += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vocabulary() -> Vocabulary {
+        let mut vocabulary = Vocabulary::default();
+        vocabulary.add("timeout = 30\ntimeouts: [1, 2]\ntimeout_ms = 5\ntime_out = 1");
+        vocabulary.add("timeout again, timeoot once");
+        vocabulary
+    }
+
+    #[test]
+    fn it_ranks_suggestions_by_distance_and_frequency() {
+        let suggestions = vocabulary().suggest("timeuot", 3);
+
+        assert_eq!(
+            suggestions,
+            vec![
+                ("timeoot".to_string(), 1, 1),
+                ("timeout".to_string(), 2, 2),
+                ("time_out".to_string(), 2, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_only_suggests_for_single_word_patterns() {
+        let patterns = vec!["timeout config".to_string(), "timeuot".to_string()];
+
+        let suggestions = vocabulary().suggest_patterns(&patterns, 1);
+
+        assert_eq!(
+            suggestions,
+            vec![Suggestion {
+                pattern: 1,
+                term: "timeoot".to_string(),
+                distance: 1,
+                frequency: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn it_finds_words_and_file_names_by_prefix() {
+        let mut vocabulary = vocabulary();
+        vocabulary.add_file(Path::new("source/Timeouts.md"), "");
+
+        assert_eq!(
+            vocabulary.words_starting_with("Time"),
+            vec!["timeout", "time_out", "timeoot", "timeout_ms", "timeouts"]
+        );
+        assert_eq!(
+            vocabulary.file_names_starting_with("time"),
+            vec!["Timeouts.md"]
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime},
+};
+
+use jwalk::{Parallelism, WalkDir};
+use rayon::{iter::Either, prelude::*};
+
+use crate::{
+    diagnostics::{self, DiagnosticKind, Diagnostics},
+    git::{self, Blobs, Revision},
+    limits::Budget,
+};
+
+/// A file read from a source, either from disk or from a git revision. Contents
+/// are text unless the file was read as raw bytes.
+pub(crate) struct SourceFile<C = String> {
+    pub(crate) path: PathBuf,
+    pub(crate) contents: C,
+}
+
+/// Tells from the name of an entry, and whether it's a directory, if the walk
+/// goes into it or reads it
+pub(crate) type Keep = fn(&str, bool) -> bool;
+
+fn keep_everything(_: &str, _: bool) -> bool {
+    true
+}
+
+/// Reads every file of a source in parallel. Files that can't be read come out as `None`.
+pub(crate) struct Walker;
+
+impl Walker {
+    /// Files as text, the ones that aren't valid UTF-8 are skipped. What goes
+    /// wrong is added to the diagnostics.
+    pub(crate) fn source_files<'source>(
+        source_path: &'source Path,
+        git_ref: Option<&str>,
+        diagnostics: &'source Diagnostics,
+    ) -> impl ParallelIterator<Item = Option<SourceFile>> + 'source {
+        Self::source_bytes(source_path, git_ref, diagnostics)
+            .map(move |source_file| Self::decode(source_file, diagnostics))
+    }
+
+    /// Files as text, read until the budget of the search runs out. What goes
+    /// wrong is added to its diagnostics.
+    pub(crate) fn source_files_within<'source>(
+        source_path: &'source Path,
+        git_ref: Option<&str>,
+        budget: &'source Budget,
+        diagnostics: &'source Diagnostics,
+    ) -> impl ParallelIterator<Item = Option<SourceFile>> + 'source {
+        Self::source_bytes_within(source_path, git_ref, budget, diagnostics)
+            .map(move |source_file| Self::decode(source_file, diagnostics))
+    }
+
+    /// Files of the working tree as text, hidden ones like `.env` included.
+    /// What's inside `.git` is left out.
+    pub(crate) fn source_files_with_hidden<'source>(
+        source_path: &Path,
+        diagnostics: &'source Diagnostics,
+    ) -> impl ParallelIterator<Item = Option<SourceFile>> + 'source {
+        Self::read_working_tree(source_path, false, keep_everything, None, diagnostics)
+            .map(move |source_file| Self::decode(source_file, diagnostics))
+    }
+
+    /// Files of the working tree as text, only the ones `keep` lets the walk
+    /// reach are read. What goes wrong is added to the diagnostics.
+    pub(crate) fn source_files_kept<'source>(
+        source_path: &Path,
+        keep: Keep,
+        diagnostics: &'source Diagnostics,
+    ) -> impl ParallelIterator<Item = Option<SourceFile>> + 'source {
+        Self::read_working_tree(source_path, true, keep, None, diagnostics)
+            .map(move |source_file| Self::decode(source_file, diagnostics))
+    }
+
+    /// Paths of the files of the working tree the walk reaches with `keep`,
+    /// for commands that only need part of each file. What goes wrong is added
+    /// to the diagnostics.
+    pub(crate) fn source_paths_kept<'source>(
+        source_path: &Path,
+        keep: Keep,
+        diagnostics: &'source Diagnostics,
+    ) -> impl ParallelIterator<Item = PathBuf> + 'source {
+        Self::walk_working_tree(source_path, true, keep, None, diagnostics)
+    }
+
+    /// Latest change to the files searched in the working tree. Directories
+    /// count too, so removing a file moves it forward as well. `None` when the
+    /// walk can't finish in the time of the budget.
+    pub(crate) fn last_modified(source_path: &Path, budget: &Budget) -> Option<SystemTime> {
+        let mut last_modified = None;
+        for result in WalkDir::new(source_path)
+            .max_depth(budget.max_depth().unwrap_or(usize::MAX))
+            .parallelism(Parallelism::Serial)
+        {
+            if budget.is_out_of_time() {
+                return None;
+            }
+            let modified = result
+                .ok()
+                .and_then(|dir_entry| dir_entry.metadata().ok()?.modified().ok());
+            last_modified = last_modified.max(modified);
+        }
+
+        last_modified
+    }
+
+    fn decode(
+        source_file: Option<SourceFile<Vec<u8>>>,
+        diagnostics: &Diagnostics,
+    ) -> Option<SourceFile> {
+        let source_file = source_file?;
+        match String::from_utf8(source_file.contents) {
+            Ok(contents) => Some(SourceFile {
+                path: source_file.path,
+                contents,
+            }),
+            Err(error) => {
+                diagnostics::report(
+                    diagnostics,
+                    DiagnosticKind::NotUtf8,
+                    &source_file.path,
+                    error.utf8_error(),
+                );
+                None
+            }
+        }
+    }
+
+    /// Files as they are. What goes wrong is added to the diagnostics.
+    pub(crate) fn source_bytes<'source>(
+        source_path: &'source Path,
+        git_ref: Option<&str>,
+        diagnostics: &'source Diagnostics,
+    ) -> impl ParallelIterator<Item = Option<SourceFile<Vec<u8>>>> + 'source {
+        Self::read(source_path, git_ref, None, diagnostics)
+    }
+
+    /// Files as they are, read until the budget of the search runs out. The ones
+    /// too large for it are skipped.
+    pub(crate) fn source_bytes_within<'source>(
+        source_path: &'source Path,
+        git_ref: Option<&str>,
+        budget: &'source Budget,
+        diagnostics: &'source Diagnostics,
+    ) -> impl ParallelIterator<Item = Option<SourceFile<Vec<u8>>>> + 'source {
+        Self::read(source_path, git_ref, Some(budget), diagnostics)
+    }
+
+    fn read<'source>(
+        source_path: &'source Path,
+        git_ref: Option<&str>,
+        budget: Option<&'source Budget>,
+        diagnostics: &'source Diagnostics,
+    ) -> impl ParallelIterator<Item = Option<SourceFile<Vec<u8>>>> + 'source {
+        match git_ref {
+            Some(git_ref) => Either::Left(Self::read_revision(
+                source_path,
+                git_ref,
+                budget,
+                diagnostics,
+            )),
+            None => Either::Right(Self::read_working_tree(
+                source_path,
+                true,
+                keep_everything,
+                budget,
+                diagnostics,
+            )),
+        }
+    }
+
+    fn read_working_tree<'source>(
+        source_path: &Path,
+        skip_hidden: bool,
+        keep: Keep,
+        budget: Option<&'source Budget>,
+        diagnostics: &'source Diagnostics,
+    ) -> impl ParallelIterator<Item = Option<SourceFile<Vec<u8>>>> + 'source {
+        Self::walk_working_tree(source_path, skip_hidden, keep, budget, diagnostics).map(
+            move |file_path| {
+                let mut file = match File::open(&file_path) {
+                    Ok(file) => file,
+                    Err(error) => {
+                        diagnostics::report(
+                            diagnostics,
+                            DiagnosticKind::of_io_error(&error),
+                            &file_path,
+                            error,
+                        );
+                        return None;
+                    }
+                };
+                if let (Some(budget), Ok(metadata)) = (budget, file.metadata()) {
+                    if !budget.fits(metadata.len()) {
+                        return None;
+                    }
+                }
+                let mut file_contents = Vec::new();
+                match file.read_to_end(&mut file_contents) {
+                    Ok(_) => {}
+                    Err(error) => {
+                        diagnostics::report(
+                            diagnostics,
+                            DiagnosticKind::of_io_error(&error),
+                            &file_path,
+                            error,
+                        );
+                        return None;
+                    }
+                };
+                Some(SourceFile {
+                    path: file_path,
+                    contents: file_contents,
+                })
+            },
+        )
+    }
+
+    /// Files of the working tree, until the budget runs out
+    fn walk_working_tree<'source>(
+        source_path: &Path,
+        skip_hidden: bool,
+        keep: Keep,
+        budget: Option<&'source Budget>,
+        diagnostics: &'source Diagnostics,
+    ) -> impl ParallelIterator<Item = PathBuf> + 'source {
+        let root = source_path.to_path_buf();
+        let max_depth = budget.and_then(Budget::max_depth);
+        let deadline = budget.and_then(Budget::deadline);
+        WalkDir::new(source_path)
+            .skip_hidden(skip_hidden)
+            .max_depth(max_depth.unwrap_or(usize::MAX))
+            // Directories are listed on one thread and the files read across the search
+            // pool, a walk that waited on the same pool could livelock
+            .parallelism(Parallelism::Serial)
+            // Hidden files may be searched, but never what's inside `.git`
+            .process_read_dir(move |_, _, _, children| {
+                children.retain(|child| match child {
+                    Ok(dir_entry) => {
+                        let name = dir_entry.file_name().to_string_lossy();
+                        name != ".git" && keep(&name, dir_entry.file_type().is_dir())
+                    }
+                    Err(_) => true,
+                });
+                // Once the time is spent, directories are still listed but not gone into,
+                // the walk ends even where there are few files to stop at
+                if matches!(deadline, Some(deadline) if Instant::now() >= deadline) {
+                    for dir_entry in children.iter_mut().flatten() {
+                        dir_entry.read_children_path = None;
+                    }
+                }
+            })
+            .into_iter()
+            .par_bridge()
+            .filter_map(move |result| match result {
+                Ok(dir_entry) => Some(dir_entry),
+                Err(error) => {
+                    let path = error.path().unwrap_or(&root);
+                    diagnostics::report(diagnostics, DiagnosticKind::WalkError, path, &error);
+                    None
+                }
+            })
+            .filter(move |dir_entry| match dir_entry.metadata() {
+                Ok(metadata) if metadata.is_dir() => {
+                    if let Some(budget) = budget {
+                        budget.leave_out(dir_entry.depth, &dir_entry.path());
+                    }
+                    false
+                }
+                Ok(_) => true,
+                Err(error) => {
+                    diagnostics::report(
+                        diagnostics,
+                        DiagnosticKind::WalkError,
+                        &dir_entry.path(),
+                        error,
+                    );
+                    false
+                }
+            })
+            .map(move |dir_entry| match budget {
+                Some(budget) if !budget.take_file() => None,
+                _ => Some(dir_entry),
+            })
+            .while_some()
+            .map(|dir_entry| dir_entry.path())
+    }
+
+    fn read_revision<'source>(
+        source_path: &'source Path,
+        git_ref: &str,
+        budget: Option<&'source Budget>,
+        diagnostics: &'source Diagnostics,
+    ) -> impl ParallelIterator<Item = Option<SourceFile<Vec<u8>>>> + 'source {
+        let blobs = Self::resolve_revision(source_path, git_ref, budget, diagnostics);
+
+        // Blobs come out of a single git process, the files are then searched in parallel
+        blobs
+            .into_iter()
+            .flatten()
+            .take_while(move |_| match budget {
+                Some(budget) => budget.take_file(),
+                None => true,
+            })
+            .par_bridge()
+            .map(move |(file_path, blob)| {
+                let bytes = match blob {
+                    Ok(bytes) => bytes,
+                    Err(error) => {
+                        diagnostics::report(
+                            diagnostics,
+                            DiagnosticKind::UnreadableFile,
+                            &source_path.join(&file_path),
+                            error,
+                        );
+                        return None;
+                    }
+                };
+                if let Some(budget) = budget {
+                    if !budget.fits(bytes.len() as u64) {
+                        return None;
+                    }
+                }
+                Some(SourceFile {
+                    path: source_path.join(file_path),
+                    contents: bytes,
+                })
+            })
+    }
+
+    fn resolve_revision(
+        source_path: &Path,
+        git_ref: &str,
+        budget: Option<&Budget>,
+        diagnostics: &Diagnostics,
+    ) -> Option<Blobs> {
+        if !git::is_repository(source_path) {
+            diagnostics::report(
+                diagnostics,
+                DiagnosticKind::UnreadableSource,
+                source_path,
+                "skipped, not a git repository",
+            );
+            return None;
+        }
+
+        let blobs = Revision::resolve(source_path, git_ref).and_then(|revision| {
+            let mut files = revision.files()?;
+            if let Some(budget) = budget {
+                budget.keep_within_depth(&mut files);
+            }
+            revision.read(files)
+        });
+        match blobs {
+            Ok(blobs) => Some(blobs),
+            Err(error) => {
+                diagnostics::report(
+                    diagnostics,
+                    DiagnosticKind::UnreadableSource,
+                    source_path,
+                    format!("unable to read it at {}, due to {}", git_ref, error),
+                );
+                None
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use config::Configuration;
+use config::{Configuration, Language};
 use url::Url;
 
 use std::{
@@ -30,7 +30,10 @@ impl<GC: GitClient> SourceManager<GC> {
     pub fn list<C: Configuration>(&self, config: &C) -> Result<()> {
         println!("List of sources");
         for source in config.get_sources().iter() {
-            println!("{}", source.display());
+            match config.get_analyzer(source) {
+                Some(language) => println!("{} ({:?} analyzer)", source.display(), language),
+                None => println!("{}", source.display()),
+            }
         }
 
         Ok(())
@@ -41,6 +44,17 @@ impl<GC: GitClient> SourceManager<GC> {
 
         Ok(())
     }
+
+    pub fn set_analyzer<P: AsRef<Path>, C: Configuration>(
+        &self,
+        config: &mut C,
+        path: P,
+        language: Option<Language>,
+    ) -> Result<()> {
+        config.set_analyzer(path, language)?;
+
+        Ok(())
+    }
 }
 
 impl Default for SourceManager<DefaultGitClient> {
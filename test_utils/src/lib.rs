@@ -1,9 +1,9 @@
-use std::{fs::{self, File}, io::Write, path::{Path, PathBuf}};
+use std::{fs::{self, File}, io::{self, Write}, path::{Path, PathBuf}, process::{Command, Output}};
 
 use tempfile::{tempdir, TempDir};
 use anyhow::Result;
 
-use config::Configuration;
+use config::{Configuration, Language};
 
 pub fn create_fake_source() -> anyhow::Result<TempDir> {
     let tempdir = tempdir()?;
@@ -20,6 +20,48 @@ pub fn create_fake_source() -> anyhow::Result<TempDir> {
     Ok(tempdir)
 }
 
+/// Creates a git repository with a tagged `v1` commit holding `file.txt`, whose
+/// working tree copy is then changed so it no longer matches the tag
+pub fn create_fake_git_source() -> anyhow::Result<TempDir> {
+    let tempdir = tempdir()?;
+
+    git(tempdir.path(), &["init", "--quiet"])?;
+    fs::write(tempdir.path().join("file.txt"), "released.test")?;
+    git(tempdir.path(), &["add", "."])?;
+    git(tempdir.path(), &["commit", "--quiet", "-m", "release"])?;
+    git(tempdir.path(), &["tag", "v1"])?;
+    fs::write(tempdir.path().join("file.txt"), "unreleased.test")?;
+
+    Ok(tempdir)
+}
+
+/// Creates a fake git source, see `create_fake_git_source`, with a `feature`
+/// branch holding `feature.txt` and `other.txt`. Returns it along with a shallow
+/// clone of it, which only knows of its default branch.
+pub fn create_fake_git_remote() -> anyhow::Result<(TempDir, TempDir)> {
+    let origin = create_fake_git_source()?;
+    git(origin.path(), &["checkout", "--quiet", "-b", "feature"])?;
+    fs::write(origin.path().join("feature.txt"), "feature.test")?;
+    fs::write(origin.path().join("other.txt"), "other feature.test")?;
+    git(origin.path(), &["add", "."])?;
+    git(origin.path(), &["commit", "--quiet", "-m", "feature"])?;
+    git(origin.path(), &["checkout", "--quiet", "-"])?;
+
+    let clone = tempdir()?;
+    let origin_url = format!("file://{}", origin.path().display());
+    git(clone.path(), &["clone", "--quiet", "--depth", "1", &origin_url, "."])?;
+
+    Ok((origin, clone))
+}
+
+fn git(directory: &Path, args: &[&str]) -> io::Result<Output> {
+    Command::new("git")
+        .args(["-c", "user.name=wicli", "-c", "user.email=wicli@example.com"])
+        .args(args)
+        .current_dir(directory)
+        .output()
+}
+
 #[derive(Debug)]
 pub struct MockConfiguration {
     pub source_added: Option<PathBuf>,
@@ -39,5 +81,21 @@ impl Configuration for MockConfiguration {
         todo!()
     }
 
+    fn set_analyzer<P: AsRef<Path>>(&mut self, _path: P, _language: Option<Language>) -> Result<()> {
+        todo!()
+    }
+
+    fn get_analyzer<P: AsRef<Path>>(&self, _path: P) -> Option<Language> {
+        todo!()
+    }
+
+    fn add_to_history(&mut self, _query: &str) {
+        todo!()
+    }
+
+    fn get_history(&self) -> &Vec<String> {
+        todo!()
+    }
+
     const DEFAULT_CONFIG_NAME: &'static str = "hello";
 }